@@ -0,0 +1,175 @@
+use crate::canvas::Canvas;
+use crate::color::Color;
+use crate::tuple::*;
+use crate::FP;
+use crate::PI;
+
+// What a ray that misses every object sees, looked up by the ray's direction alone
+#[derive(Debug, Clone)]
+pub enum Background {
+    Color(Color),
+    Gradient { top: Color, bottom: Color },
+    // Faces in the order right (+x), left (-x), up (+y), down (-y), front (+z), back (-z)
+    CubeMap(Box<[Canvas; 6]>),
+    Equirectangular(Canvas),
+}
+
+impl Background {
+    pub fn read_equirectangular(path: &str) -> Result<Self, String> {
+        Ok(Background::Equirectangular(Canvas::read_ppm(path)?))
+    }
+
+    pub fn color_at(&self, direction: &Tuple) -> Color {
+        let d = direction.normalize();
+
+        match self {
+            Background::Color(c) => *c,
+            Background::Gradient { top, bottom } => {
+                let t = (d.y + 1.0) / 2.0;
+                *bottom + (*top - *bottom) * t
+            }
+            Background::CubeMap(faces) => {
+                let (face, u, v) = cube_map_uv(&d);
+                faces[face].uv_color_at(u, v)
+            }
+            Background::Equirectangular(image) => {
+                let (u, v) = direction_to_uv(&d);
+                image.uv_color_at(u, v)
+            }
+        }
+    }
+}
+
+// Longitude and latitude of a unit direction mapped into 0.0..=1.0, with -z at the centre of
+// the image and +y at the top
+pub fn direction_to_uv(d: &Tuple) -> (FP, FP) {
+    let u = 0.5 + d.x.atan2(-d.z) / (2.0 * PI);
+    let v = 0.5 + d.y.clamp(-1.0, 1.0).asin() / PI;
+    (u, v)
+}
+
+pub fn uv_to_direction(u: FP, v: FP) -> Tuple {
+    let phi = (u - 0.5) * 2.0 * PI;
+    let theta = (v - 0.5) * PI;
+    vector(
+        theta.cos() * phi.sin(),
+        theta.sin(),
+        -theta.cos() * phi.cos(),
+    )
+}
+
+// Projects the direction onto the face of a unit cube along its largest component
+fn cube_map_uv(d: &Tuple) -> (usize, FP, FP) {
+    let (ax, ay, az) = (d.x.abs(), d.y.abs(), d.z.abs());
+
+    let (face, u, v) = if ax >= ay && ax >= az {
+        if d.x > 0.0 {
+            (0, -d.z / ax, d.y / ax)
+        } else {
+            (1, d.z / ax, d.y / ax)
+        }
+    } else if ay >= az {
+        if d.y > 0.0 {
+            (2, d.x / ay, -d.z / ay)
+        } else {
+            (3, d.x / ay, d.z / ay)
+        }
+    } else if d.z > 0.0 {
+        (4, d.x / az, d.y / az)
+    } else {
+        (5, -d.x / az, d.y / az)
+    };
+
+    (face, (u + 1.0) / 2.0, (v + 1.0) / 2.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::background::*;
+
+    #[test]
+    fn constant_background() {
+        let b = Background::Color(Color::blue());
+        assert_eq!(Color::blue(), b.color_at(&vector(1.0, 2.0, 3.0)));
+    }
+
+    #[test]
+    fn vertical_gradient_background() {
+        let b = Background::Gradient {
+            top: Color::white(),
+            bottom: Color::black(),
+        };
+
+        assert_eq!(Color::white(), b.color_at(&vector(0.0, 1.0, 0.0)));
+        assert_eq!(Color::black(), b.color_at(&vector(0.0, -1.0, 0.0)));
+        assert_eq!(
+            Color::new(0.5, 0.5, 0.5),
+            b.color_at(&vector(1.0, 0.0, 0.0))
+        );
+    }
+
+    #[test]
+    fn identifying_cube_face_from_direction() {
+        let cases = [
+            (vector(-1.0, 0.5, -0.25), 1),
+            (vector(1.1, -0.75, 0.8), 0),
+            (vector(0.1, 0.6, 0.9), 4),
+            (vector(-0.7, 0.0, -2.0), 5),
+            (vector(0.5, 1.0, 0.9), 2),
+            (vector(-0.2, -1.3, 1.1), 3),
+        ];
+
+        for (d, face) in cases {
+            assert_eq!(face, cube_map_uv(&d).0);
+        }
+    }
+
+    #[test]
+    fn cube_map_background() {
+        let colors = [
+            Color::red(),
+            Color::new(1.0, 1.0, 0.0),
+            Color::green(),
+            Color::new(0.0, 1.0, 1.0),
+            Color::blue(),
+            Color::new(1.0, 0.0, 1.0),
+        ];
+        let faces = colors.map(|c| {
+            let mut canvas = Canvas::new(1, 1);
+            canvas.fill(c);
+            canvas
+        });
+        let b = Background::CubeMap(Box::new(faces));
+
+        assert_eq!(colors[0], b.color_at(&vector(1.0, 0.1, 0.1)));
+        assert_eq!(colors[3], b.color_at(&vector(0.1, -1.0, 0.1)));
+        assert_eq!(colors[5], b.color_at(&vector(0.1, 0.1, -1.0)));
+    }
+
+    #[test]
+    fn equirectangular_mapping_round_trips() {
+        let cases = [
+            (vector(0.0, 0.0, -1.0), (0.5, 0.5)),
+            (vector(1.0, 0.0, 0.0), (0.75, 0.5)),
+            (vector(-1.0, 0.0, 0.0), (0.25, 0.5)),
+            (vector(0.0, 0.5, -0.5).normalize(), (0.5, 0.75)),
+        ];
+
+        for (d, (u, v)) in cases {
+            let uv = direction_to_uv(&d);
+            assert!((u - uv.0).abs() < crate::EPSILON);
+            assert!((v - uv.1).abs() < crate::EPSILON);
+            assert_eq!(d, uv_to_direction(uv.0, uv.1));
+        }
+    }
+
+    #[test]
+    fn equirectangular_background() {
+        let mut image = Canvas::new(4, 3);
+        image.write_pixel(2, 1, Color::red());
+        let b = Background::Equirectangular(image);
+
+        assert_eq!(Color::red(), b.color_at(&vector(0.0, 0.0, -1.0)));
+        assert_eq!(Color::black(), b.color_at(&vector(0.0, 0.0, 1.0)));
+    }
+}
@@ -0,0 +1,279 @@
+use crate::matrix::*;
+use crate::ray::Ray;
+use crate::tuple::*;
+use crate::EPSILON;
+use crate::FP;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct BoundingBox {
+    pub min: Tuple,
+    pub max: Tuple,
+}
+
+impl BoundingBox {
+    pub fn new(min: Tuple, max: Tuple) -> Self {
+        Self { min, max }
+    }
+
+    pub fn empty() -> Self {
+        Self::new(
+            point(FP::INFINITY, FP::INFINITY, FP::INFINITY),
+            point(-FP::INFINITY, -FP::INFINITY, -FP::INFINITY),
+        )
+    }
+
+    pub fn infinite() -> Self {
+        Self::new(
+            point(-FP::INFINITY, -FP::INFINITY, -FP::INFINITY),
+            point(FP::INFINITY, FP::INFINITY, FP::INFINITY),
+        )
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.min.x > self.max.x || self.min.y > self.max.y || self.min.z > self.max.z
+    }
+
+    pub fn is_finite(&self) -> bool {
+        !self.is_empty()
+            && [self.min, self.max]
+                .iter()
+                .all(|p| p.x.is_finite() && p.y.is_finite() && p.z.is_finite())
+    }
+
+    pub fn add_point(&mut self, p: Tuple) {
+        self.min = point(
+            self.min.x.min(p.x),
+            self.min.y.min(p.y),
+            self.min.z.min(p.z),
+        );
+        self.max = point(
+            self.max.x.max(p.x),
+            self.max.y.max(p.y),
+            self.max.z.max(p.z),
+        );
+    }
+
+    pub fn add_box(&mut self, other: &BoundingBox) {
+        if other.is_empty() {
+            return;
+        }
+
+        self.add_point(other.min);
+        self.add_point(other.max);
+    }
+
+    pub fn contains_point(&self, p: Tuple) -> bool {
+        (self.min.x..=self.max.x).contains(&p.x)
+            && (self.min.y..=self.max.y).contains(&p.y)
+            && (self.min.z..=self.max.z).contains(&p.z)
+    }
+
+    pub fn contains_box(&self, other: &BoundingBox) -> bool {
+        self.contains_point(other.min) && self.contains_point(other.max)
+    }
+
+    pub fn centroid(&self) -> Tuple {
+        point(
+            (self.min.x + self.max.x) / 2.0,
+            (self.min.y + self.max.y) / 2.0,
+            (self.min.z + self.max.z) / 2.0,
+        )
+    }
+
+    pub fn surface_area(&self) -> FP {
+        if self.is_empty() {
+            return 0.0;
+        }
+
+        let d = self.max - self.min;
+        2.0 * (d.x * d.y + d.y * d.z + d.z * d.x)
+    }
+
+    // Transforming all eight corners keeps the result axis-aligned; unbounded boxes stay
+    // unbounded since multiplying an infinity by a zero matrix entry is undefined
+    pub fn transform(&self, m: Matrix4) -> BoundingBox {
+        if self.is_empty() {
+            return *self;
+        }
+
+        if !self.is_finite() {
+            return Self::infinite();
+        }
+
+        let mut out = Self::empty();
+        for x in [self.min.x, self.max.x] {
+            for y in [self.min.y, self.max.y] {
+                for z in [self.min.z, self.max.z] {
+                    out.add_point(m * point(x, y, z));
+                }
+            }
+        }
+        out
+    }
+
+    // Tests the whole line rather than just t >= 0, so anything the ray could report an
+    // intersection with (including those behind its origin) is never culled
+    pub fn intersects(&self, r: &Ray) -> bool {
+        if self.is_empty() {
+            return false;
+        }
+
+        let mut tmin = -FP::INFINITY;
+        let mut tmax = FP::INFINITY;
+
+        for axis in 0..3 {
+            let origin = r.origin.get(axis);
+            let direction = r.direction.get(axis);
+            let min = self.min.get(axis) - EPSILON;
+            let max = self.max.get(axis) + EPSILON;
+
+            if direction.abs() < EPSILON {
+                if origin < min || origin > max {
+                    return false;
+                }
+                continue;
+            }
+
+            let mut t0 = (min - origin) / direction;
+            let mut t1 = (max - origin) / direction;
+            if t0 > t1 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+
+            tmin = tmin.max(t0);
+            tmax = tmax.min(t1);
+
+            if tmin > tmax {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bounds::*;
+    use std::f64::consts::PI;
+
+    #[test]
+    fn creating_empty_bounding_box() {
+        let b = BoundingBox::empty();
+        assert!(b.is_empty());
+        assert_eq!(FP::INFINITY, b.min.x);
+        assert_eq!(-FP::INFINITY, b.max.x);
+    }
+
+    #[test]
+    fn adding_points_to_empty_box() {
+        let mut b = BoundingBox::empty();
+        b.add_point(point(-5.0, 2.0, 0.0));
+        b.add_point(point(7.0, 0.0, -3.0));
+
+        assert_eq!(point(-5.0, 0.0, -3.0), b.min);
+        assert_eq!(point(7.0, 2.0, 0.0), b.max);
+    }
+
+    #[test]
+    fn adding_one_box_to_another() {
+        let mut b1 = BoundingBox::new(point(-5.0, -2.0, 0.0), point(7.0, 4.0, 4.0));
+        let b2 = BoundingBox::new(point(8.0, -7.0, -2.0), point(14.0, 2.0, 8.0));
+        b1.add_box(&b2);
+
+        assert_eq!(point(-5.0, -7.0, -2.0), b1.min);
+        assert_eq!(point(14.0, 4.0, 8.0), b1.max);
+    }
+
+    #[test]
+    fn box_contains_point() {
+        let b = BoundingBox::new(point(5.0, -2.0, 0.0), point(11.0, 4.0, 7.0));
+        let cases = [
+            (point(5.0, -2.0, 0.0), true),
+            (point(11.0, 4.0, 7.0), true),
+            (point(8.0, 1.0, 3.0), true),
+            (point(3.0, 0.0, 3.0), false),
+            (point(8.0, -4.0, 3.0), false),
+            (point(8.0, 1.0, -1.0), false),
+            (point(13.0, 1.0, 3.0), false),
+            (point(8.0, 5.0, 3.0), false),
+            (point(8.0, 1.0, 8.0), false),
+        ];
+
+        for (p, result) in cases {
+            assert_eq!(result, b.contains_point(p));
+        }
+    }
+
+    #[test]
+    fn box_contains_box() {
+        let b = BoundingBox::new(point(5.0, -2.0, 0.0), point(11.0, 4.0, 7.0));
+        let cases = [
+            (point(5.0, -2.0, 0.0), point(11.0, 4.0, 7.0), true),
+            (point(6.0, -1.0, 1.0), point(10.0, 3.0, 6.0), true),
+            (point(4.0, -3.0, -1.0), point(10.0, 3.0, 6.0), false),
+            (point(6.0, -1.0, 1.0), point(12.0, 5.0, 8.0), false),
+        ];
+
+        for (min, max, result) in cases {
+            assert_eq!(result, b.contains_box(&BoundingBox::new(min, max)));
+        }
+    }
+
+    #[test]
+    fn transforming_bounding_box() {
+        let b = BoundingBox::new(point(-1.0, -1.0, -1.0), point(1.0, 1.0, 1.0));
+        let b2 = b.transform(rotation_x(PI / 4.0) * rotation_y(PI / 4.0));
+
+        assert_eq!(point(-1.41421, -1.70711, -1.70711), b2.min);
+        assert_eq!(point(1.41421, 1.70711, 1.70711), b2.max);
+    }
+
+    #[test]
+    fn transforming_unbounded_box_stays_unbounded() {
+        let b = BoundingBox::new(
+            point(-FP::INFINITY, 0.0, -FP::INFINITY),
+            point(FP::INFINITY, 0.0, FP::INFINITY),
+        );
+        assert!(!b.transform(translation(0.0, 1.0, 0.0)).is_finite());
+    }
+
+    #[test]
+    fn surface_area_of_box() {
+        let b = BoundingBox::new(point(0.0, 0.0, 0.0), point(1.0, 2.0, 3.0));
+        assert_eq!(22.0, b.surface_area());
+        assert_eq!(0.0, BoundingBox::empty().surface_area());
+    }
+
+    #[test]
+    fn intersecting_ray_with_bounding_box() {
+        let b = BoundingBox::new(point(5.0, -2.0, 0.0), point(11.0, 4.0, 7.0));
+        let cases = [
+            (point(15.0, 1.0, 2.0), vector(-1.0, 0.0, 0.0), true),
+            (point(-5.0, -1.0, 4.0), vector(1.0, 0.0, 0.0), true),
+            (point(7.0, 6.0, 5.0), vector(0.0, -1.0, 0.0), true),
+            (point(9.0, -5.0, 6.0), vector(0.0, 1.0, 0.0), true),
+            (point(8.0, 2.0, 12.0), vector(0.0, 0.0, -1.0), true),
+            (point(6.0, 0.0, -5.0), vector(0.0, 0.0, 1.0), true),
+            (point(8.0, 1.0, 3.5), vector(0.0, 0.0, 1.0), true),
+            (point(9.0, -1.0, -8.0), vector(2.0, 4.0, 6.0), false),
+            (point(8.0, 3.0, -4.0), vector(6.0, 2.0, 4.0), false),
+            (point(9.0, -1.0, -2.0), vector(4.0, 6.0, 2.0), false),
+            (point(4.0, 0.0, 9.0), vector(0.0, 0.0, -1.0), false),
+            (point(8.0, 6.0, -1.0), vector(0.0, -1.0, 0.0), false),
+            (point(12.0, 5.0, 4.0), vector(-1.0, 0.0, 0.0), false),
+        ];
+
+        for (origin, direction, result) in cases {
+            let r = Ray::new(origin, direction.normalize());
+            assert_eq!(result, b.intersects(&r));
+        }
+    }
+
+    #[test]
+    fn box_behind_ray_is_still_intersected() {
+        let b = BoundingBox::new(point(-1.0, -1.0, -1.0), point(1.0, 1.0, 1.0));
+        let r = Ray::new(point(0.0, 0.0, 5.0), vector(0.0, 0.0, 1.0));
+        assert!(b.intersects(&r));
+    }
+}
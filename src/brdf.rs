@@ -0,0 +1,111 @@
+use crate::color::Color;
+use crate::FP;
+use crate::PI;
+
+// Reflectance at normal incidence of a typical dielectric such as plastic or paint
+pub const DIELECTRIC_F0: FP = 0.04;
+
+// A roughness of exactly 0.0 would make the GGX lobe infinitely narrow
+const MIN_ALPHA: FP = 0.002;
+
+// Artists think of roughness as perceptually linear, which squaring maps onto the GGX width
+pub fn alpha(roughness: FP) -> FP {
+    (roughness * roughness).max(MIN_ALPHA)
+}
+
+// GGX (Trowbridge-Reitz) distribution of microfacet normals around the surface normal
+pub fn ggx_distribution(n_dot_h: FP, alpha: FP) -> FP {
+    let a2 = alpha * alpha;
+    let d = n_dot_h * n_dot_h * (a2 - 1.0) + 1.0;
+    a2 / (PI * d * d)
+}
+
+// The fraction of microfacets neither shadowed from the light nor masked from the eye, using
+// the separable Smith form of GGX
+pub fn smith_geometry(n_dot_v: FP, n_dot_l: FP, alpha: FP) -> FP {
+    let a2 = alpha * alpha;
+    let g1 = |cos: FP| 2.0 * cos / (cos + (a2 + (1.0 - a2) * cos * cos).sqrt());
+    g1(n_dot_v) * g1(n_dot_l)
+}
+
+// Schlick's approximation, with a colored reflectance at normal incidence so that conductors
+// tint their reflections
+pub fn fresnel_schlick(f0: Color, cos: FP) -> Color {
+    f0 + (Color::white() - f0) * (1.0 - cos.clamp(0.0, 1.0)).powi(5)
+}
+
+// How much of the light arriving from every direction a glossy lobe reflects towards the eye,
+// from Karis' fitted approximation. Mirror reflections are weighted by this rather than by
+// the Fresnel term alone, so rough surfaces don't reflect more than their lobe would
+pub fn specular_albedo(f0: Color, roughness: FP, n_dot_v: FP) -> Color {
+    let (c0, c1, c2, c3) = (
+        -roughness + 1.0,
+        -0.0275 * roughness + 0.0425,
+        -0.572 * roughness + 1.04,
+        0.022 * roughness - 0.04,
+    );
+    let a004 = (c0 * c0).min((-9.28 * n_dot_v.max(0.0)).exp2()) * c0 + c1;
+    let scale = -1.04 * a004 + c2;
+    let bias = 1.04 * a004 + c3;
+
+    f0 * scale + Color::white() * bias
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::brdf::*;
+
+    #[test]
+    fn ggx_distribution_integrates_to_one() {
+        // Projected onto the surface, the microfacet normals cover it exactly once
+        let alpha = alpha(0.5);
+        let steps = 10000;
+        let mut sum = 0.0;
+        for i in 0..steps {
+            let theta = (i as FP + 0.5) / steps as FP * PI / 2.0;
+            let d_theta = PI / 2.0 / steps as FP;
+            sum += ggx_distribution(theta.cos(), alpha)
+                * theta.cos()
+                * theta.sin()
+                * d_theta
+                * 2.0
+                * PI;
+        }
+
+        assert!((1.0 - sum).abs() < 0.001);
+    }
+
+    #[test]
+    fn rougher_surfaces_have_lower_peaks() {
+        assert!(ggx_distribution(1.0, alpha(0.2)) > ggx_distribution(1.0, alpha(0.8)));
+        assert!(ggx_distribution(0.7, alpha(0.2)) < ggx_distribution(0.7, alpha(0.8)));
+    }
+
+    #[test]
+    fn smith_geometry_at_normal_incidence() {
+        assert!((1.0 - smith_geometry(1.0, 1.0, alpha(0.7))).abs() < crate::EPSILON);
+        assert!(smith_geometry(0.1, 1.0, alpha(0.7)) < smith_geometry(0.1, 1.0, alpha(0.2)));
+    }
+
+    #[test]
+    fn fresnel_rises_to_white_at_grazing_angles() {
+        let gold = Color::new(1.0, 0.71, 0.29);
+
+        assert_eq!(gold, fresnel_schlick(gold, 1.0));
+        assert_eq!(Color::white(), fresnel_schlick(gold, 0.0));
+
+        let f0 = Color::white() * DIELECTRIC_F0;
+        assert!(fresnel_schlick(f0, 0.2).red > fresnel_schlick(f0, 0.8).red);
+    }
+
+    #[test]
+    fn specular_albedo_of_smooth_and_rough_surfaces() {
+        let f0 = Color::white() * DIELECTRIC_F0;
+        let smooth = specular_albedo(f0, 0.0, 1.0);
+        let rough = specular_albedo(f0, 1.0, 1.0);
+
+        assert!((DIELECTRIC_F0 - smooth.red).abs() < 0.01);
+        assert!(rough.red < smooth.red);
+        assert!(specular_albedo(f0, 0.0, 0.05).red > 0.5);
+    }
+}
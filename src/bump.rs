@@ -0,0 +1,234 @@
+use crate::canvas::Canvas;
+use crate::light::luminance;
+use crate::pattern::Pattern;
+use crate::sampling::orthonormal_basis;
+use crate::shape::Shape;
+use crate::tuple::*;
+use crate::EPSILON;
+use crate::FP;
+use crate::PI;
+
+// Distance between the points a height field or texture mapping is compared at, in world space
+const DELTA: FP = 0.0001;
+
+// How a point in object space is wrapped onto a texture image
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum UvMapping {
+    // Repeats every unit along x and z
+    Planar,
+    Spherical,
+    // Around the y axis, repeating every unit along it
+    Cylindrical,
+}
+
+impl UvMapping {
+    pub fn uv(&self, p: &Tuple) -> (FP, FP) {
+        match self {
+            UvMapping::Planar => (p.x.rem_euclid(1.0), p.z.rem_euclid(1.0)),
+            UvMapping::Spherical => {
+                let radius = vector(p.x, p.y, p.z).magnitude();
+                let polar = (p.y / radius).clamp(-1.0, 1.0).acos();
+                (longitude(p), 1.0 - polar / PI)
+            }
+            UvMapping::Cylindrical => (longitude(p), p.y.rem_euclid(1.0)),
+        }
+    }
+}
+
+fn longitude(p: &Tuple) -> FP {
+    1.0 - (p.x.atan2(p.z) / (2.0 * PI) + 0.5)
+}
+
+// Surface detail that tilts the shading normal without changing the geometry
+#[derive(Debug, PartialEq, Clone)]
+pub enum Bump {
+    // The brightness of the pattern is taken as a height above the surface, scaled by `scale`
+    Height { pattern: Box<Pattern>, scale: FP },
+    // Each pixel encodes a normal in tangent space, with red along u, green along v and blue
+    // out of the surface
+    NormalMap { map: Canvas, mapping: UvMapping },
+}
+
+impl Bump {
+    pub fn height(pattern: Pattern, scale: FP) -> Self {
+        Bump::Height {
+            pattern: Box::new(pattern),
+            scale,
+        }
+    }
+
+    pub fn normal_map(map: Canvas, mapping: UvMapping) -> Self {
+        Bump::NormalMap { map, mapping }
+    }
+
+    // Both kinds are worked out with finite differences across the surface at `point`, so
+    // they follow the object however it's transformed
+    pub fn perturb(&self, obj: &Shape, point: &Tuple, normalv: &Tuple) -> Tuple {
+        let (t, b) = orthonormal_basis(normalv);
+
+        match self {
+            Bump::Height { pattern, scale } => {
+                let height = |p: Tuple| luminance(&pattern.color_at_object(obj, &p));
+                let slope = |d: Tuple| {
+                    (height(*point + d * DELTA) - height(*point - d * DELTA)) / (2.0 * DELTA)
+                };
+
+                (*normalv - (t * slope(t) + b * slope(b)) * *scale).normalize()
+            }
+            Bump::NormalMap { map, mapping } => {
+                let uv = |p: Tuple| mapping.uv(&obj.world_to_object(p));
+                let (u, v) = uv(*point);
+
+                // The direction along the surface in which u increases, allowing for u
+                // wrapping around from 1.0 back to 0.0
+                let du = |d: Tuple| {
+                    let delta = uv(*point + d * DELTA).0 - u;
+                    delta - delta.round()
+                };
+                let dv = |d: Tuple| uv(*point + d * DELTA).1 - v;
+
+                // Where u doesn't change across the surface, as with planar mapping on a face
+                // that's side on to it, any direction along the surface has to do instead
+                let along_u = t * du(t) + b * du(b);
+                let tangent = if along_u.magnitude() > DELTA * EPSILON {
+                    along_u.normalize()
+                } else {
+                    t
+                };
+                let mut bitangent = normalv.cross(&tangent);
+                if bitangent.dot(&(t * dv(t) + b * dv(b))) < 0.0 {
+                    bitangent = -bitangent;
+                }
+
+                let c = map.uv_color_at(u, v);
+                let n = tangent * (2.0 * c.red - 1.0)
+                    + bitangent * (2.0 * c.green - 1.0)
+                    + *normalv * (2.0 * c.blue - 1.0);
+                n.normalize()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bump::*;
+    use crate::color::Color;
+    use crate::matrix::*;
+
+    #[test]
+    fn planar_mapping() {
+        let cases = [
+            (point(0.25, 0.0, 0.5), (0.25, 0.5)),
+            (point(0.25, 0.0, -0.25), (0.25, 0.75)),
+            (point(0.25, 0.5, -0.25), (0.25, 0.75)),
+            (point(1.25, 0.0, 0.5), (0.25, 0.5)),
+            (point(0.0, 0.0, 0.0), (0.0, 0.0)),
+        ];
+
+        for (p, expected) in cases {
+            assert_eq!(expected, UvMapping::Planar.uv(&p));
+        }
+    }
+
+    #[test]
+    fn spherical_mapping() {
+        let s = (2.0 as FP).sqrt() / 2.0;
+        let cases = [
+            (point(0.0, 0.0, -1.0), (0.0, 0.5)),
+            (point(1.0, 0.0, 0.0), (0.25, 0.5)),
+            (point(0.0, 0.0, 1.0), (0.5, 0.5)),
+            (point(-1.0, 0.0, 0.0), (0.75, 0.5)),
+            (point(0.0, 1.0, 0.0), (0.5, 1.0)),
+            (point(0.0, -1.0, 0.0), (0.5, 0.0)),
+            (point(s, s, 0.0), (0.25, 0.75)),
+        ];
+
+        for (p, (u, v)) in cases {
+            let uv = UvMapping::Spherical.uv(&p);
+            assert!((u - uv.0).abs() < crate::EPSILON);
+            assert!((v - uv.1).abs() < crate::EPSILON);
+        }
+    }
+
+    #[test]
+    fn cylindrical_mapping() {
+        let uv = UvMapping::Cylindrical.uv(&point(1.0, 1.5, 0.0));
+
+        assert!((0.25 - uv.0).abs() < crate::EPSILON);
+        assert!((0.5 - uv.1).abs() < crate::EPSILON);
+    }
+
+    #[test]
+    fn flat_height_field_leaves_normal_unchanged() {
+        let bump = Bump::height(Pattern::solid(Color::white()), 1.0);
+        let n = vector(0.0, 1.0, 0.0);
+
+        assert_eq!(n, bump.perturb(&Shape::plane(), &point(0.3, 0.0, 0.7), &n));
+    }
+
+    #[test]
+    fn height_field_tilts_normal_down_its_slope() {
+        // Brightness rises along x, so the surface slopes up that way and the normal leans back
+        let mut pattern = Pattern::gradient(Color::black(), Color::white());
+        pattern.set_transform(scaling(10.0, 1.0, 1.0));
+        let bump = Bump::height(pattern, 1.0);
+        let n = vector(0.0, 1.0, 0.0);
+
+        let perturbed = bump.perturb(&Shape::plane(), &point(2.5, 0.0, 0.0), &n);
+        assert!(perturbed.x < 0.0);
+        assert!(perturbed.z.abs() < crate::EPSILON);
+        assert!((1.0 - perturbed.magnitude()).abs() < crate::EPSILON);
+    }
+
+    #[test]
+    fn flat_normal_map_leaves_normal_unchanged() {
+        let mut map = Canvas::new(4, 4);
+        map.fill(Color::new(0.5, 0.5, 1.0));
+        let bump = Bump::normal_map(map, UvMapping::Planar);
+        let n = vector(0.0, 1.0, 0.0);
+
+        assert_eq!(n, bump.perturb(&Shape::plane(), &point(0.3, 0.0, 0.6), &n));
+    }
+
+    #[test]
+    fn normal_map_tilts_normal_along_u() {
+        let mut map = Canvas::new(4, 4);
+        map.fill(Color::new(1.0, 0.5, 0.5));
+        let bump = Bump::normal_map(map, UvMapping::Planar);
+        let n = vector(0.0, 1.0, 0.0);
+
+        // Planar u runs along x, and the map points straight along it
+        let perturbed = bump.perturb(&Shape::plane(), &point(0.3, 0.0, 0.6), &n);
+        assert_eq!(vector(1.0, 0.0, 0.0), perturbed);
+    }
+
+    #[test]
+    fn planar_normal_map_on_side_of_cube() {
+        let mut map = Canvas::new(4, 4);
+        map.fill(Color::new(0.5, 0.5, 1.0));
+        let flat = Bump::normal_map(map.clone(), UvMapping::Planar);
+        map.fill(Color::new(1.0, 0.5, 0.5));
+        let tilted = Bump::normal_map(map, UvMapping::Planar);
+        let n = vector(1.0, 0.0, 0.0);
+        let p = point(1.0, 0.3, 0.6);
+
+        assert_eq!(n, flat.perturb(&Shape::cube(), &p, &n));
+        let perturbed = tilted.perturb(&Shape::cube(), &p, &n);
+        assert!((1.0 - perturbed.magnitude()).abs() < crate::EPSILON);
+        assert!(perturbed.dot(&n).abs() < crate::EPSILON);
+    }
+
+    #[test]
+    fn normal_map_follows_object_transform() {
+        let mut map = Canvas::new(4, 4);
+        map.fill(Color::new(1.0, 0.5, 0.5));
+        let bump = Bump::normal_map(map, UvMapping::Planar);
+        let mut plane = Shape::plane();
+        plane.set_transform(rotation_y(PI / 2.0));
+        let n = vector(0.0, 1.0, 0.0);
+
+        let perturbed = bump.perturb(&plane, &point(0.3, 0.0, 0.6), &n);
+        assert_eq!(vector(0.0, 0.0, -1.0), perturbed);
+    }
+}
@@ -0,0 +1,321 @@
+use crate::bounds::BoundingBox;
+use crate::intersection::*;
+use crate::ray::Ray;
+use crate::shape::Shape;
+use crate::tuple::*;
+use crate::FP;
+
+const BINS: usize = 12;
+const MAX_LEAF_SIZE: usize = 4;
+const TRAVERSAL_COST: FP = 0.125;
+
+#[derive(Debug)]
+enum Node {
+    Leaf {
+        bounds: BoundingBox,
+        start: usize,
+        count: usize,
+    },
+    Interior {
+        bounds: BoundingBox,
+        left: usize,
+        right: usize,
+    },
+}
+
+struct Primitive {
+    index: usize,
+    bounds: BoundingBox,
+    centroid: Tuple,
+}
+
+#[derive(Debug)]
+pub struct Bvh {
+    nodes: Vec<Node>,
+    indices: Vec<usize>,
+    unbounded: Vec<usize>,
+}
+
+impl Bvh {
+    pub fn build(objects: &[Shape]) -> Self {
+        let mut prims = vec![];
+        let mut unbounded = vec![];
+
+        for (index, o) in objects.iter().enumerate() {
+            let bounds = o.bounds();
+            if bounds.is_empty() {
+                continue;
+            }
+
+            // Planes and untruncated cylinders can't be partitioned so they're always tested
+            if bounds.is_finite() {
+                prims.push(Primitive {
+                    index,
+                    bounds,
+                    centroid: bounds.centroid(),
+                });
+            } else {
+                unbounded.push(index);
+            }
+        }
+
+        let mut bvh = Self {
+            nodes: vec![],
+            indices: vec![],
+            unbounded,
+        };
+
+        if !prims.is_empty() {
+            bvh.build_node(&mut prims, 0);
+        }
+        bvh.indices = prims.iter().map(|p| p.index).collect();
+
+        bvh
+    }
+
+    fn build_node(&mut self, prims: &mut [Primitive], start: usize) -> usize {
+        let mut bounds = BoundingBox::empty();
+        let mut centroids = BoundingBox::empty();
+        for p in prims.iter() {
+            bounds.add_box(&p.bounds);
+            centroids.add_point(p.centroid);
+        }
+
+        let node = self.nodes.len();
+        self.nodes.push(Node::Leaf {
+            bounds,
+            start,
+            count: prims.len(),
+        });
+
+        if let Some(mid) = Self::split(prims, &bounds, &centroids) {
+            let (l, r) = prims.split_at_mut(mid);
+            let left = self.build_node(l, start);
+            let right = self.build_node(r, start + mid);
+            self.nodes[node] = Node::Interior {
+                bounds,
+                left,
+                right,
+            };
+        }
+
+        node
+    }
+
+    // Bins the primitives along the widest centroid axis and partitions them at the split with
+    // the lowest surface area heuristic cost, or returns None if a single leaf is cheaper
+    fn split(
+        prims: &mut [Primitive],
+        bounds: &BoundingBox,
+        centroids: &BoundingBox,
+    ) -> Option<usize> {
+        if prims.len() <= 1 {
+            return None;
+        }
+
+        let extent = centroids.max - centroids.min;
+        let axis = if extent.x >= extent.y && extent.x >= extent.z {
+            0
+        } else if extent.y >= extent.z {
+            1
+        } else {
+            2
+        };
+
+        let min = centroids.min.get(axis);
+        let width = extent.get(axis);
+        if width <= 0.0 {
+            return None;
+        }
+
+        let bin_of = |p: &Primitive| {
+            (((p.centroid.get(axis) - min) / width * BINS as FP) as usize).min(BINS - 1)
+        };
+
+        let mut counts = [0; BINS];
+        let mut bin_bounds = [BoundingBox::empty(); BINS];
+        for p in prims.iter() {
+            let b = bin_of(p);
+            counts[b] += 1;
+            bin_bounds[b].add_box(&p.bounds);
+        }
+
+        let mut best_cost = FP::INFINITY;
+        let mut best_split = 0;
+        for split in 1..BINS {
+            let mut left = BoundingBox::empty();
+            let mut right = BoundingBox::empty();
+            let mut left_count = 0;
+            let mut right_count = 0;
+
+            for b in 0..split {
+                left.add_box(&bin_bounds[b]);
+                left_count += counts[b];
+            }
+            for b in split..BINS {
+                right.add_box(&bin_bounds[b]);
+                right_count += counts[b];
+            }
+
+            if left_count == 0 || right_count == 0 {
+                continue;
+            }
+
+            let cost = TRAVERSAL_COST * bounds.surface_area()
+                + left.surface_area() * left_count as FP
+                + right.surface_area() * right_count as FP;
+            if cost < best_cost {
+                best_cost = cost;
+                best_split = split;
+            }
+        }
+
+        let leaf_cost = bounds.surface_area() * prims.len() as FP;
+        if best_cost == FP::INFINITY || (best_cost >= leaf_cost && prims.len() <= MAX_LEAF_SIZE) {
+            return None;
+        }
+
+        let mut mid = 0;
+        for i in 0..prims.len() {
+            if bin_of(&prims[i]) < best_split {
+                prims.swap(i, mid);
+                mid += 1;
+            }
+        }
+
+        Some(mid)
+    }
+
+    // Candidates are intersected in the order they appear in the world so that, once sorted,
+    // the result is exactly what testing every object would have produced
+    pub fn intersect<'a>(&self, objects: &'a [Shape], r: &Ray) -> Intersections<'a> {
+        let mut candidates = self.unbounded.clone();
+        let mut stack = vec![];
+        if !self.nodes.is_empty() {
+            stack.push(0);
+        }
+
+        while let Some(n) = stack.pop() {
+            match &self.nodes[n] {
+                Node::Leaf {
+                    bounds,
+                    start,
+                    count,
+                } => {
+                    if bounds.intersects(r) {
+                        candidates.extend_from_slice(&self.indices[*start..start + count]);
+                    }
+                }
+                Node::Interior {
+                    bounds,
+                    left,
+                    right,
+                } => {
+                    if bounds.intersects(r) {
+                        stack.push(*right);
+                        stack.push(*left);
+                    }
+                }
+            }
+        }
+
+        candidates.sort_unstable();
+
+        let mut v: Vec<Intersection> = candidates
+            .iter()
+            .flat_map(|&i| objects[i].intersect(r).intersections)
+            .collect();
+        v.sort();
+        Intersections::new(v)
+    }
+
+    pub fn depth(&self) -> usize {
+        self.node_depth(0)
+    }
+
+    fn node_depth(&self, n: usize) -> usize {
+        match self.nodes.get(n) {
+            None | Some(Node::Leaf { .. }) => 1,
+            Some(Node::Interior { left, right, .. }) => {
+                1 + self.node_depth(*left).max(self.node_depth(*right))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bvh::*;
+    use crate::matrix::*;
+
+    fn grid_of_spheres(n: usize) -> Vec<Shape> {
+        let mut objects = vec![];
+        for x in 0..n {
+            for y in 0..n {
+                for z in 0..n {
+                    let mut s = Shape::sphere();
+                    s.set_transform(translation(x as FP * 3.0, y as FP * 3.0, z as FP * 3.0));
+                    objects.push(s);
+                }
+            }
+        }
+        objects
+    }
+
+    #[test]
+    fn empty_bvh_has_no_intersections() {
+        let bvh = Bvh::build(&[]);
+        let r = Ray::new(point(0.0, 0.0, -5.0), vector(0.0, 0.0, 1.0));
+        assert_eq!(0, bvh.intersect(&[], &r).len());
+    }
+
+    #[test]
+    fn unbounded_shapes_are_always_tested() {
+        let objects = vec![Shape::plane()];
+        let bvh = Bvh::build(&objects);
+        assert_eq!(vec![0], bvh.unbounded);
+
+        let r = Ray::new(point(100.0, 1.0, 100.0), vector(0.0, -1.0, 0.0));
+        assert_eq!(1, bvh.intersect(&objects, &r).len());
+    }
+
+    #[test]
+    fn bvh_splits_large_scenes() {
+        let objects = grid_of_spheres(4);
+        let bvh = Bvh::build(&objects);
+
+        assert!(bvh.depth() > 2);
+        assert_eq!(objects.len(), bvh.indices.len());
+    }
+
+    #[test]
+    fn bvh_matches_testing_every_object() {
+        let mut objects = grid_of_spheres(4);
+        objects.push(Shape::plane());
+        let bvh = Bvh::build(&objects);
+
+        let rays = [
+            Ray::new(point(-5.0, 0.0, 0.0), vector(1.0, 0.0, 0.0)),
+            Ray::new(point(4.5, 4.5, -10.0), vector(0.0, 0.0, 1.0)),
+            Ray::new(point(-5.0, -5.0, -5.0), vector(1.0, 1.0, 1.0).normalize()),
+            Ray::new(point(20.0, 3.0, 3.0), vector(-1.0, 0.1, 0.0).normalize()),
+            Ray::new(point(3.0, 20.0, 6.0), vector(0.0, -1.0, 0.0)),
+        ];
+
+        for r in rays.iter() {
+            let mut expected: Vec<Intersection> = objects
+                .iter()
+                .flat_map(|o| o.intersect(r).intersections)
+                .collect();
+            expected.sort();
+
+            let actual = bvh.intersect(&objects, r);
+
+            assert_eq!(expected.len(), actual.len());
+            for (e, a) in expected.iter().zip(actual.intersections.iter()) {
+                assert_eq!(e.t, a.t);
+                assert_eq!(e.obj.get_id(), a.obj.get_id());
+            }
+        }
+    }
+}
@@ -1,10 +1,19 @@
 use crate::canvas::Canvas;
+use crate::color::Color;
 use crate::matrix::*;
 use crate::ray::Ray;
 use crate::tuple::*;
 use crate::world::World;
 use crate::FP;
 
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum RenderMode {
+    // One ray per pixel, shaded with Phong lighting plus mirror reflection and refraction
+    Whitted,
+    // The average of `samples` randomly jittered paths per pixel, including indirect light
+    PathTraced { samples: usize },
+}
+
 pub struct Camera {
     pub hsize: usize,
     pub vsize: usize,
@@ -13,6 +22,8 @@ pub struct Camera {
     pub fov: FP,
     pub transform: Matrix4,
     pub pixel_size: FP,
+    pub max_depth: usize,
+    pub mode: RenderMode,
 }
 
 impl Camera {
@@ -34,12 +45,19 @@ impl Camera {
             fov,
             pixel_size: (half_width * 2.0) / (hsize as FP),
             transform: Matrix4::identity(),
+            max_depth: 5,
+            mode: RenderMode::Whitted,
         }
     }
 
     pub fn ray_for_pixel(&self, px: usize, py: usize) -> Ray {
-        let xoffset = ((px as FP) + 0.5) * self.pixel_size;
-        let yoffset = ((py as FP) + 0.5) * self.pixel_size;
+        self.ray_for_pixel_offset(px, py, 0.5, 0.5)
+    }
+
+    // A ray through the point (dx, dy) within the pixel, each from 0.0 to 1.0
+    pub fn ray_for_pixel_offset(&self, px: usize, py: usize, dx: FP, dy: FP) -> Ray {
+        let xoffset = ((px as FP) + dx) * self.pixel_size;
+        let yoffset = ((py as FP) + dy) * self.pixel_size;
 
         let world_x = self.half_width - xoffset;
         let world_y = self.half_height - yoffset;
@@ -56,18 +74,32 @@ impl Camera {
 
         for x in 0..(self.hsize - 1) {
             for y in 0..(self.vsize - 1) {
-                let ray = self.ray_for_pixel(x, y);
-                let color = w.color_at(&ray);
-                image.write_pixel(x, y, color);
+                image.write_pixel(x, y, self.color_for_pixel(w, x, y));
             }
         }
 
         image
     }
+
+    fn color_for_pixel(&self, w: &World, x: usize, y: usize) -> Color {
+        match self.mode {
+            RenderMode::Whitted => w.color_at(&self.ray_for_pixel(x, y), self.max_depth),
+            RenderMode::PathTraced { samples } => {
+                let samples = samples.max(1);
+                let mut sum = Color::black();
+                for _ in 0..samples {
+                    let ray = self.ray_for_pixel_offset(x, y, rand::random(), rand::random());
+                    sum = sum + w.trace_path(&ray, 0, self.max_depth);
+                }
+                sum * (1.0 / samples as FP)
+            }
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::background::Background;
     use crate::camera::*;
     use crate::color::*;
     use crate::EPSILON;
@@ -141,4 +173,24 @@ mod tests {
         let image = c.render(&w);
         assert_eq!(image.pixel_at(5, 5), Color::new(0.38066, 0.47583, 0.2855));
     }
+
+    #[test]
+    fn cameras_render_whitted_by_default() {
+        let c = Camera::new(11, 11, PI / 2.0);
+        assert_eq!(RenderMode::Whitted, c.mode);
+    }
+
+    #[test]
+    fn path_traced_render_averages_samples() {
+        let mut w = World::new();
+        w.background = Background::Color(Color::new(0.2, 0.4, 0.6));
+        let mut c = Camera::new(5, 5, PI / 2.0);
+        c.mode = RenderMode::PathTraced { samples: 4 };
+        let image = c.render(&w);
+
+        assert_eq!(Color::new(0.2, 0.4, 0.6), image.pixel_at(2, 2));
+
+        c.mode = RenderMode::PathTraced { samples: 0 };
+        assert_eq!(Color::new(0.2, 0.4, 0.6), c.render(&w).pixel_at(2, 2));
+    }
 }
@@ -2,6 +2,7 @@ use crate::color::Color;
 
 use crate::FP;
 
+#[derive(Debug, PartialEq, Clone)]
 pub struct Canvas {
     pub width: usize,
     pub height: usize,
@@ -25,7 +26,7 @@ impl Canvas {
     }
 
     pub fn pixel_at(&self, x: usize, y: usize) -> Color {
-        self.pixels.get(self.index(x, y)).unwrap().clone()
+        *self.pixels.get(self.index(x, y)).unwrap()
     }
 
     #[inline]
@@ -66,6 +67,244 @@ impl Canvas {
             *p = c;
         }
     }
+
+    // Reads plain (P3) and binary (P6) PPM images, scaling channels into 0.0..=1.0
+    pub fn from_ppm(data: &[u8]) -> Result<Self, String> {
+        let mut pos = 0;
+        let mut header = vec![];
+        while header.len() < 4 {
+            header.push(next_token(data, &mut pos).ok_or("PPM header is truncated")?);
+        }
+
+        let magic = header[0];
+        let parse = |t: &[u8]| -> Result<usize, String> {
+            std::str::from_utf8(t)
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .ok_or(format!("Invalid PPM value {:?}", String::from_utf8_lossy(t)))
+        };
+        let width = parse(header[1])?;
+        let height = parse(header[2])?;
+        let maxval = parse(header[3])?;
+        if width == 0 || height == 0 || maxval == 0 {
+            return Err("PPM width, height and maximum value must be positive".to_string());
+        }
+        if maxval > 65535 {
+            return Err("PPM maximum value must be below 65536".to_string());
+        }
+
+        let count = width
+            .checked_mul(height)
+            .and_then(|n| n.checked_mul(3))
+            .ok_or("PPM image is too large")?;
+        let values: Vec<FP> = match magic {
+            b"P3" => {
+                // Nothing is reserved up front, since the header may claim far more pixels
+                // than the data holds
+                let mut values = vec![];
+                while values.len() < count {
+                    let t = next_token(data, &mut pos).ok_or("PPM pixel data is truncated")?;
+                    values.push(parse(t)? as FP);
+                }
+                values
+            }
+            b"P6" => {
+                // A single whitespace byte separates the header from the binary data, and
+                // samples above 255 take two bytes each, most significant first
+                let sample_len = if maxval > 255 { 2 } else { 1 };
+                let start = pos + 1;
+                let bytes = count
+                    .checked_mul(sample_len)
+                    .and_then(|len| start.checked_add(len))
+                    .and_then(|end| data.get(start..end))
+                    .ok_or("PPM pixel data is truncated")?;
+                bytes
+                    .chunks(sample_len)
+                    .map(|b| b.iter().fold(0, |acc, &b| acc * 256 + b as usize) as FP)
+                    .collect()
+            }
+            _ => return Err("Only P3 and P6 PPM images are supported".to_string()),
+        };
+
+        let maxval = maxval as FP;
+        let mut canvas = Canvas::new(width, height);
+        for (p, rgb) in canvas.pixels.iter_mut().zip(values.chunks(3)) {
+            *p = Color::new(rgb[0] / maxval, rgb[1] / maxval, rgb[2] / maxval);
+        }
+        Ok(canvas)
+    }
+
+    pub fn read_ppm(path: &str) -> Result<Self, String> {
+        let data = std::fs::read(path).map_err(|e| format!("{}: {}", path, e))?;
+        Self::from_ppm(&data)
+    }
+
+    // Reads Radiance RGBE (.hdr) images, either flat or with run-length encoded scanlines.
+    // Values aren't clamped, so bright areas such as the sun can be far above 1.0
+    pub fn from_hdr(data: &[u8]) -> Result<Self, String> {
+        let mut pos = 0;
+        let mut next_line = || -> Result<&[u8], String> {
+            let start = pos;
+            while *data.get(pos).ok_or("HDR header is truncated")? != b'\n' {
+                pos += 1;
+            }
+            pos += 1;
+            Ok(&data[start..pos - 1])
+        };
+
+        if !next_line()?.starts_with(b"#?") {
+            return Err("Not a Radiance HDR image".to_string());
+        }
+        loop {
+            let line = next_line()?;
+            if line.is_empty() {
+                break;
+            }
+            if line.starts_with(b"FORMAT=") && line != b"FORMAT=32-bit_rle_rgbe" {
+                return Err("Only RGBE HDR images are supported".to_string());
+            }
+        }
+
+        let resolution = String::from_utf8_lossy(next_line()?).to_string();
+        let (height, width) = match resolution.split_whitespace().collect::<Vec<_>>()[..] {
+            ["-Y", h, "+X", w] => (
+                h.parse::<usize>().map_err(|e| e.to_string())?,
+                w.parse::<usize>().map_err(|e| e.to_string())?,
+            ),
+            _ => return Err(format!("Unsupported HDR orientation {}", resolution)),
+        };
+        if width == 0 || height == 0 {
+            return Err("HDR width and height must be positive".to_string());
+        }
+        let scanline_len = width.checked_mul(4).ok_or("HDR image is too large")?;
+        if height.checked_mul(scanline_len).is_none() {
+            return Err("HDR image is too large".to_string());
+        }
+
+        // Even run-length encoded, a scanline takes a header and two bytes for each run of up
+        // to 127 pixels in every channel, so dimensions the data can't possibly hold are
+        // rejected before anything is allocated for them
+        let min_scanline_len = if (8..0x8000).contains(&width) {
+            4 + 8 * width.div_ceil(127)
+        } else {
+            scanline_len
+        };
+        if height.saturating_mul(min_scanline_len) > data.len() - pos {
+            return Err("HDR pixel data is truncated".to_string());
+        }
+
+        let mut canvas = Canvas::new(width, height);
+        let mut scanline = vec![0u8; scanline_len];
+        for y in 0..height {
+            read_hdr_scanline(data, &mut pos, &mut scanline)?;
+            for x in 0..width {
+                let rgbe = &scanline[x * 4..x * 4 + 4];
+                canvas.write_pixel(x, y, rgbe_to_color(rgbe));
+            }
+        }
+        Ok(canvas)
+    }
+
+    pub fn read_hdr(path: &str) -> Result<Self, String> {
+        let data = std::fs::read(path).map_err(|e| format!("{}: {}", path, e))?;
+        Self::from_hdr(&data)
+    }
+
+    // The pixel covering texture coordinates u and v in 0.0..=1.0, with v = 1.0 at the top.
+    // Anything looking an image up by direction goes through here, so that a background and
+    // the light it casts always agree on which pixel a direction falls in
+    pub fn uv_to_pixel(&self, u: FP, v: FP) -> (usize, usize) {
+        let x = (u.clamp(0.0, 1.0) * self.width as FP) as usize;
+        let y = ((1.0 - v.clamp(0.0, 1.0)) * self.height as FP) as usize;
+        (x.min(self.width - 1), y.min(self.height - 1))
+    }
+
+    pub fn uv_color_at(&self, u: FP, v: FP) -> Color {
+        let (x, y) = self.uv_to_pixel(u, v);
+        self.pixel_at(x, y)
+    }
+}
+
+fn read_hdr_scanline(data: &[u8], pos: &mut usize, scanline: &mut [u8]) -> Result<(), String> {
+    let width = scanline.len() / 4;
+
+    let rle = (8..0x8000).contains(&width)
+        && data.get(*pos..*pos + 2) == Some(&[2, 2])
+        && data.get(*pos + 2).is_some_and(|b| b & 0x80 == 0);
+    if !rle {
+        for b in scanline.iter_mut() {
+            *b = next_byte(data, pos)?;
+        }
+        return Ok(());
+    }
+
+    let header = data.get(*pos..*pos + 4).ok_or("HDR pixel data is truncated")?;
+    let encoded_width = u16::from_be_bytes([header[2], header[3]]) as usize;
+    if encoded_width != width {
+        return Err("HDR scanline width doesn't match the image".to_string());
+    }
+    *pos += 4;
+
+    // Each channel of the scanline is stored separately as runs and literal spans
+    for channel in 0..4 {
+        let mut x = 0;
+        while x < width {
+            let count = next_byte(data, pos)? as usize;
+            let (count, run) = if count > 128 {
+                (count - 128, Some(next_byte(data, pos)?))
+            } else {
+                (count, None)
+            };
+            if count == 0 || x + count > width {
+                return Err("Corrupt HDR scanline".to_string());
+            }
+
+            for _ in 0..count {
+                scanline[x * 4 + channel] = match run {
+                    Some(b) => b,
+                    None => next_byte(data, pos)?,
+                };
+                x += 1;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn next_byte(data: &[u8], pos: &mut usize) -> Result<u8, String> {
+    let b = *data.get(*pos).ok_or("HDR pixel data is truncated")?;
+    *pos += 1;
+    Ok(b)
+}
+
+fn rgbe_to_color(rgbe: &[u8]) -> Color {
+    if rgbe[3] == 0 {
+        return Color::black();
+    }
+
+    let f = (2.0 as FP).powi(rgbe[3] as i32 - 136);
+    Color::new(rgbe[0] as FP * f, rgbe[1] as FP * f, rgbe[2] as FP * f)
+}
+
+// Skips whitespace and comments, returning the next whitespace-separated token of a PPM
+fn next_token<'a>(data: &'a [u8], pos: &mut usize) -> Option<&'a [u8]> {
+    loop {
+        match data.get(*pos)? {
+            b'#' => {
+                while *data.get(*pos)? != b'\n' {
+                    *pos += 1;
+                }
+            }
+            b if b.is_ascii_whitespace() => *pos += 1,
+            _ => break,
+        }
+    }
+
+    let start = *pos;
+    while data.get(*pos).is_some_and(|b| !b.is_ascii_whitespace()) {
+        *pos += 1;
+    }
+    Some(&data[start..*pos])
 }
 
 #[cfg(test)]
@@ -142,4 +381,110 @@ mod tests {
         let canvas = Canvas::new(5, 3);
         assert!(canvas.to_ppm().ends_with("\n"));
     }
+
+    #[test]
+    fn reading_plain_ppm() {
+        let ppm = b"P3\n# a comment\n2 2\n255\n255 0 0  0 255 0\n0 0 255  255 255 255\n";
+        let canvas = Canvas::from_ppm(ppm).unwrap();
+
+        assert_eq!(2, canvas.width);
+        assert_eq!(2, canvas.height);
+        assert_eq!(Color::red(), canvas.pixel_at(0, 0));
+        assert_eq!(Color::green(), canvas.pixel_at(1, 0));
+        assert_eq!(Color::blue(), canvas.pixel_at(0, 1));
+        assert_eq!(Color::white(), canvas.pixel_at(1, 1));
+    }
+
+    #[test]
+    fn reading_ppm_scales_by_maximum_value() {
+        let canvas = Canvas::from_ppm(b"P3 1 1 100 50 100 0").unwrap();
+        assert_eq!(Color::new(0.5, 1.0, 0.0), canvas.pixel_at(0, 0));
+    }
+
+    #[test]
+    fn reading_binary_ppm() {
+        let mut ppm = b"P6\n2 1\n255\n".to_vec();
+        ppm.extend_from_slice(&[255, 0, 0, 0, 0, 255]);
+        let canvas = Canvas::from_ppm(&ppm).unwrap();
+
+        assert_eq!(Color::red(), canvas.pixel_at(0, 0));
+        assert_eq!(Color::blue(), canvas.pixel_at(1, 0));
+    }
+
+    #[test]
+    fn reading_16_bit_binary_ppm() {
+        let mut ppm = b"P6\n1 1\n1000\n".to_vec();
+        ppm.extend_from_slice(&[0x03, 0xe8, 0x01, 0xf4, 0x00, 0x00]);
+        let canvas = Canvas::from_ppm(&ppm).unwrap();
+
+        assert_eq!(Color::new(1.0, 0.5, 0.0), canvas.pixel_at(0, 0));
+        assert!(Canvas::from_ppm(&ppm[..ppm.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn reading_invalid_ppm() {
+        assert!(Canvas::from_ppm(b"P5 1 1 255 0").is_err());
+        assert!(Canvas::from_ppm(b"P3 2 2 255 0 0 0").is_err());
+        assert!(Canvas::from_ppm(b"P3 0 0 255").is_err());
+        assert!(Canvas::from_ppm(b"P3 1 1 0 0 0 0").is_err());
+        assert!(Canvas::from_ppm(b"P6 4294967296 4294967296 255 ").is_err());
+        assert!(Canvas::from_ppm(b"P3 100000 100000 255 0").is_err());
+        assert!(Canvas::from_ppm(b"P3 1 1 65536 0 0 0").is_err());
+    }
+
+    #[test]
+    fn sampling_canvas_by_uv() {
+        let mut canvas = Canvas::new(2, 2);
+        canvas.write_pixel(0, 0, Color::red());
+        canvas.write_pixel(1, 1, Color::blue());
+
+        assert_eq!(Color::red(), canvas.uv_color_at(0.0, 1.0));
+        assert_eq!(Color::blue(), canvas.uv_color_at(1.0, 0.0));
+        assert_eq!(Color::black(), canvas.uv_color_at(0.9, 0.9));
+    }
+
+    #[test]
+    fn uv_falls_in_pixel_covering_it() {
+        let canvas = Canvas::new(4, 2);
+
+        assert_eq!((0, 0), canvas.uv_to_pixel(0.0, 1.0));
+        assert_eq!((0, 0), canvas.uv_to_pixel(0.24, 0.51));
+        assert_eq!((1, 1), canvas.uv_to_pixel(0.26, 0.49));
+        assert_eq!((3, 1), canvas.uv_to_pixel(1.0, 0.0));
+    }
+
+    #[test]
+    fn reading_flat_hdr() {
+        let mut hdr = b"#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y 1 +X 2\n".to_vec();
+        hdr.extend_from_slice(&[128, 64, 0, 129, 128, 128, 128, 136]);
+        let canvas = Canvas::from_hdr(&hdr).unwrap();
+
+        assert_eq!(Color::new(1.0, 0.5, 0.0), canvas.pixel_at(0, 0));
+        assert_eq!(Color::new(128.0, 128.0, 128.0), canvas.pixel_at(1, 0));
+    }
+
+    #[test]
+    fn reading_run_length_encoded_hdr() {
+        let mut hdr = b"#?RADIANCE\n\n-Y 1 +X 8\n".to_vec();
+        hdr.extend_from_slice(&[2, 2, 0, 8]);
+        hdr.extend_from_slice(&[136, 128]);
+        hdr.extend_from_slice(&[4, 0, 0, 0, 0, 132, 64]);
+        hdr.extend_from_slice(&[136, 0]);
+        hdr.extend_from_slice(&[136, 129]);
+        let canvas = Canvas::from_hdr(&hdr).unwrap();
+
+        assert_eq!(Color::new(1.0, 0.0, 0.0), canvas.pixel_at(0, 0));
+        assert_eq!(Color::new(1.0, 0.5, 0.0), canvas.pixel_at(7, 0));
+    }
+
+    #[test]
+    fn reading_invalid_hdr() {
+        assert!(Canvas::from_hdr(b"P3 1 1 255 0 0 0").is_err());
+        assert!(Canvas::from_hdr(b"#?RADIANCE\n\n+Y 1 +X 1\n\x80\x80\x80\x81").is_err());
+        assert!(Canvas::from_hdr(b"#?RADIANCE\n\n-Y 1 +X 2\n\x80").is_err());
+        assert!(Canvas::from_hdr(b"#?RADIANCE\n\n-Y 1 +X 8\n\x02\x02\x00").is_err());
+        assert!(Canvas::from_hdr(b"#?RADIANCE\n\n-Y 0 +X 0\n").is_err());
+        assert!(Canvas::from_hdr(b"#?RADIANCE\n\n-Y 100000 +X 100000\n").is_err());
+        assert!(Canvas::from_hdr(b"#?RADIANCE\n\n-Y 1 +X 100000000000\n").is_err());
+    }
 }
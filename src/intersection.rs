@@ -1,4 +1,7 @@
+use crate::brdf::alpha;
+use crate::material::{Parameter, Shading};
 use crate::ray::Ray;
+use crate::sampling::ggx_half_vector;
 use crate::shape::*;
 use crate::tuple::*;
 use crate::EPSILON;
@@ -7,80 +10,209 @@ use std::ops;
 
 use crate::FP;
 
-#[derive(Debug, Clone)]
-pub struct Intersection {
+#[derive(Debug, Clone, Copy)]
+pub struct Intersection<'a> {
     pub t: FP,
-    pub obj: Shape,
+    pub obj: &'a Shape,
+    pub u: FP,
+    pub v: FP,
 }
 
 #[derive(Debug)]
-pub struct Computations {
+pub struct Computations<'a> {
     pub t: FP,
-    pub obj: Shape,
+    pub obj: &'a Shape,
+    // How the object's material shades the hit, with any mapped parameters worked out there
+    pub shading: Shading,
     pub point: Tuple,
     pub over_point: Tuple,
+    pub under_point: Tuple,
     pub eyev: Tuple,
     pub normalv: Tuple,
+    pub reflectv: Tuple,
     pub inside: bool,
+    pub n1: FP,
+    pub n2: FP,
 }
 
-impl Intersection {
-    pub fn new(t: FP, obj: Shape) -> Self {
-        Self { t, obj }
+impl<'a> Intersection<'a> {
+    pub fn new(t: FP, obj: &'a Shape) -> Self {
+        Self::new_with_uv(t, obj, 0.0, 0.0)
     }
 
-    pub fn prepare_computations(&self, r: &Ray) -> Computations {
+    pub fn new_with_uv(t: FP, obj: &'a Shape, u: FP, v: FP) -> Self {
+        Self { t, obj, u, v }
+    }
+
+    // `xs` is every intersection along `r`, which is needed to work out which objects the hit
+    // lies inside of and so the refractive indices on either side of it
+    pub fn prepare_computations(&self, r: &Ray, xs: &Intersections<'a>) -> Computations<'a> {
         let point = r.position(self.t);
         let eyev = -r.direction;
-        let mut normalv = self.obj.normal_at(point);
+        let mut normalv = self.obj.normal_at_hit(point, self);
+
+        // Bump mapping only changes how the surface is shaded, so the geometric normal is kept
+        // for deciding which side the eye is on and for offsetting the over and under points
+        let mut shading_normalv = match &self.obj.material.bump {
+            Some(bump) => bump.perturb(self.obj, &point, &normalv),
+            None => normalv,
+        };
 
         let inside = normalv.dot(&eyev) < 0.0;
 
         if inside {
             normalv = -normalv;
+            shading_normalv = -shading_normalv;
         }
 
-        let over_point = point + (normalv * (EPSILON / 10000000.0) );
+        let over_point = point + normalv * EPSILON;
+        let under_point = point - normalv * EPSILON;
+        let normalv = shading_normalv;
+        let reflectv = r.direction.reflect(&normalv);
+        let (n1, n2) = self.refractive_indices(xs, &point);
 
         Computations {
             t: self.t,
-            obj: self.obj.clone(),
+            obj: self.obj,
+            shading: self.obj.material.at(self.obj, &point),
             point,
             over_point,
+            under_point,
             eyev,
             normalv,
+            reflectv,
             inside,
+            n1,
+            n2,
         }
     }
+
+    fn refractive_indices(&self, xs: &Intersections<'a>, point: &Tuple) -> (FP, FP) {
+        let mut containers: Vec<&Shape> = vec![];
+        let mut n1 = 1.0;
+
+        for i in xs.intersections.iter() {
+            let is_hit = i.t == self.t && i.obj.get_id() == self.obj.get_id();
+
+            if is_hit {
+                n1 = containers.last().map_or(1.0, |o| refractive_index(o, point));
+            }
+
+            match containers.iter().position(|o| o.get_id() == i.obj.get_id()) {
+                Some(index) => {
+                    containers.remove(index);
+                }
+                None => containers.push(i.obj),
+            }
+
+            if is_hit {
+                let n2 = containers.last().map_or(1.0, |o| refractive_index(o, point));
+                return (n1, n2);
+            }
+        }
+
+        (n1, 1.0)
+    }
 }
 
-impl PartialOrd for Intersection {
+fn refractive_index(obj: &Shape, point: &Tuple) -> FP {
+    obj.material.parameter_at(Parameter::RefractiveIndex, obj, point)
+}
+
+impl Computations<'_> {
+    // Snell's law, with no refracted direction at all under total internal reflection
+    pub fn refractv(&self) -> Option<Tuple> {
+        self.refract_about(&self.normalv)
+    }
+
+    fn refract_about(&self, normalv: &Tuple) -> Option<Tuple> {
+        let n_ratio = self.n1 / self.n2;
+        let cos_i = self.eyev.dot(normalv);
+        let sin2_t = n_ratio * n_ratio * (1.0 - cos_i * cos_i);
+        if sin2_t > 1.0 {
+            return None;
+        }
+
+        let cos_t = (1.0 - sin2_t).sqrt();
+        Some(*normalv * (n_ratio * cos_i - cos_t) - self.eyev * n_ratio)
+    }
+
+    // A rough surface is treated as being made of tiny facets, so reflecting off one chosen at
+    // random scatters the reflection into a lobe around the mirror direction. Facets that would
+    // send it into the surface fall back to the mirror direction
+    pub fn glossy_reflectv(&self, roughness: FP) -> Tuple {
+        if roughness <= 0.0 {
+            return self.reflectv;
+        }
+
+        let facet = ggx_half_vector(&self.normalv, alpha(roughness));
+        let reflectv = (-self.eyev).reflect(&facet);
+        if reflectv.dot(&self.normalv) > 0.0 {
+            reflectv
+        } else {
+            self.reflectv
+        }
+    }
+
+    // Refraction through a randomly chosen facet, frosting whatever is seen through the surface
+    pub fn glossy_refractv(&self, roughness: FP) -> Option<Tuple> {
+        if roughness <= 0.0 {
+            return self.refractv();
+        }
+
+        let facet = ggx_half_vector(&self.normalv, alpha(roughness));
+        match self.refract_about(&facet) {
+            Some(refractv) if refractv.dot(&self.normalv) < 0.0 => Some(refractv),
+            _ => self.refractv(),
+        }
+    }
+
+    // Schlick's approximation of the Fresnel equations, giving the fraction of light reflected
+    pub fn schlick(&self) -> FP {
+        let mut cos = self.eyev.dot(&self.normalv);
+
+        if self.n1 > self.n2 {
+            let n = self.n1 / self.n2;
+            let sin2_t = n * n * (1.0 - cos * cos);
+            if sin2_t > 1.0 {
+                return 1.0;
+            }
+
+            cos = (1.0 - sin2_t).sqrt();
+        }
+
+        let r0 = ((self.n1 - self.n2) / (self.n1 + self.n2)).powi(2);
+        r0 + (1.0 - r0) * (1.0 - cos).powi(5)
+    }
+}
+
+impl PartialOrd for Intersection<'_> {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
         Some(self.cmp(other))
     }
 }
 
-impl Ord for Intersection {
+impl Ord for Intersection<'_> {
     fn cmp(&self, other: &Self) -> Ordering {
         self.t.partial_cmp(&other.t).unwrap()
     }
 }
 
-impl PartialEq for Intersection {
+impl PartialEq for Intersection<'_> {
     fn eq(&self, other: &Self) -> bool {
         self.t == other.t
     }
 }
 
-impl Eq for Intersection {}
+impl Eq for Intersection<'_> {}
 
 #[derive(Debug, PartialEq)]
-pub struct Intersections {
-    pub intersections: Vec<Intersection>,
+pub struct Intersections<'a> {
+    pub intersections: Vec<Intersection<'a>>,
 }
 
-impl Intersections {
-    pub fn new(intersections: Vec<Intersection>) -> Self {
+impl<'a> Intersections<'a> {
+    pub fn new(intersections: Vec<Intersection<'a>>) -> Self {
         Self { intersections }
     }
 
@@ -88,19 +220,17 @@ impl Intersections {
         self.intersections.len()
     }
 
-    pub fn hit(&self) -> Option<Intersection> {
-        let mut candidates = self
-            .intersections
+    pub fn hit(&self) -> Option<Intersection<'a>> {
+        self.intersections
             .iter()
             .filter(|i| i.t >= 0.0)
-            .collect::<Vec<&Intersection>>();
-        candidates.sort_by(|a, b| a.t.partial_cmp(&b.t).unwrap());
-        candidates.get(0).map(|x| x.to_owned().clone())
+            .min_by(|a, b| a.t.partial_cmp(&b.t).unwrap())
+            .copied()
     }
 }
 
-impl ops::Index<usize> for Intersections {
-    type Output = Intersection;
+impl<'a> ops::Index<usize> for Intersections<'a> {
+    type Output = Intersection<'a>;
 
     fn index(&self, index: usize) -> &Self::Output {
         &self.intersections[index]
@@ -109,15 +239,18 @@ impl ops::Index<usize> for Intersections {
 
 #[cfg(test)]
 mod tests {
+    use crate::bump::{Bump, UvMapping};
+    use crate::canvas::Canvas;
+    use crate::color::Color;
     use crate::intersection::*;
     use crate::matrix::*;
 
     #[test]
     fn interections() {
         let s = Shape::sphere();
-        let a = Intersection::new(1.0, s.clone());
-        let b = Intersection::new(2.0, s);
-        let i = Intersections::new(vec![a.clone(), b]);
+        let a = Intersection::new(1.0, &s);
+        let b = Intersection::new(2.0, &s);
+        let i = Intersections::new(vec![a, b]);
 
         assert_eq!(2, i.len());
         assert_eq!(a, i[0]);
@@ -126,9 +259,9 @@ mod tests {
     #[test]
     fn hit_all_pos() {
         let s = Shape::sphere();
-        let a = Intersection::new(1.0, s.clone());
-        let b = Intersection::new(2.0, s);
-        let i = Intersections::new(vec![a.clone(), b]);
+        let a = Intersection::new(1.0, &s);
+        let b = Intersection::new(2.0, &s);
+        let i = Intersections::new(vec![a, b]);
 
         assert_eq!(a, i.hit().unwrap());
     }
@@ -136,9 +269,9 @@ mod tests {
     #[test]
     fn hit_neg_pos() {
         let s = Shape::sphere();
-        let a = Intersection::new(-1.0, s.clone());
-        let b = Intersection::new(2.0, s);
-        let i = Intersections::new(vec![a, b.clone()]);
+        let a = Intersection::new(-1.0, &s);
+        let b = Intersection::new(2.0, &s);
+        let i = Intersections::new(vec![a, b]);
 
         assert_eq!(b, i.hit().unwrap());
     }
@@ -146,8 +279,8 @@ mod tests {
     #[test]
     fn hit_neg() {
         let s = Shape::sphere();
-        let a = Intersection::new(-1.0, s.clone());
-        let b = Intersection::new(-2.0, s);
+        let a = Intersection::new(-1.0, &s);
+        let b = Intersection::new(-2.0, &s);
         let i = Intersections::new(vec![a, b]);
 
         assert_eq!(None, i.hit());
@@ -156,11 +289,11 @@ mod tests {
     #[test]
     fn hit_order() {
         let s = Shape::sphere();
-        let a = Intersection::new(5.0, s.clone());
-        let b = Intersection::new(7.0, s.clone());
-        let c = Intersection::new(-3.0, s.clone());
-        let d = Intersection::new(2.0, s);
-        let i = Intersections::new(vec![a, b, c, d.clone()]);
+        let a = Intersection::new(5.0, &s);
+        let b = Intersection::new(7.0, &s);
+        let c = Intersection::new(-3.0, &s);
+        let d = Intersection::new(2.0, &s);
+        let i = Intersections::new(vec![a, b, c, d]);
 
         assert_eq!(d, i.hit().unwrap());
     }
@@ -169,8 +302,8 @@ mod tests {
     fn precomputing_state_of_intersection() {
         let r = Ray::new(point(0.0, 0.0, -5.0), vector(0.0, 0.0, 1.0));
         let shape = Shape::sphere();
-        let i = Intersection::new(4.0, shape);
-        let comps = i.prepare_computations(&r);
+        let i = Intersection::new(4.0, &shape);
+        let comps = i.prepare_computations(&r, &Intersections::new(vec![i]));
         assert_eq!(comps.t, i.t);
         assert_eq!(comps.obj.get_id(), i.obj.get_id());
         assert_eq!(comps.point, point(0.0, 0.0, -1.0));
@@ -182,18 +315,18 @@ mod tests {
     fn precomputing_state_of_intersection_outside() {
         let r = Ray::new(point(0.0, 0.0, -5.0), vector(0.0, 0.0, 1.0));
         let shape = Shape::sphere();
-        let i = Intersection::new(4.0, shape);
-        let comps = i.prepare_computations(&r);
-        assert_eq!(comps.inside, false);
+        let i = Intersection::new(4.0, &shape);
+        let comps = i.prepare_computations(&r, &Intersections::new(vec![i]));
+        assert!(!comps.inside);
     }
 
     #[test]
     fn precomputing_state_of_intersection_inside() {
         let r = Ray::new(point(0.0, 0.0, 0.0), vector(0.0, 0.0, 1.0));
         let shape = Shape::sphere();
-        let i = Intersection::new(1.0, shape);
-        let comps = i.prepare_computations(&r);
-        assert_eq!(comps.inside, true);
+        let i = Intersection::new(1.0, &shape);
+        let comps = i.prepare_computations(&r, &Intersections::new(vec![i]));
+        assert!(comps.inside);
         assert_eq!(comps.point, point(0.0, 0.0, 1.0));
         assert_eq!(comps.eyev, vector(0.0, 0.0, -1.0));
         assert_eq!(comps.normalv, vector(0.0, 0.0, -1.0));
@@ -203,11 +336,183 @@ mod tests {
     fn hit_should_offset_the_point() {
         let r = Ray::new(point(0.0, 0.0, -5.0), vector(0.0, 0.0, 1.0));
         let mut shape = Shape::sphere();
-        shape.transform = translation(0.0, 0.0, 1.0);
-        let i = Intersection::new(5.0, shape);
-        let comps = i.prepare_computations(&r);
+        shape.set_transform(translation(0.0, 0.0, 1.0));
+        let i = Intersection::new(5.0, &shape);
+        let comps = i.prepare_computations(&r, &Intersections::new(vec![i]));
 
         assert!(comps.over_point.z < -EPSILON / 2.0);
         assert!(comps.point.z > comps.over_point.z);
     }
+
+    #[test]
+    fn bump_map_tilts_shading_normal_only() {
+        let mut map = Canvas::new(2, 2);
+        map.fill(Color::new(1.0, 0.5, 0.5));
+        let mut shape = Shape::plane();
+        shape.material.bump = Some(Bump::normal_map(map, UvMapping::Planar));
+        let r = Ray::new(point(0.3, 1.0, 0.6), vector(0.0, -1.0, 0.0));
+        let i = Intersection::new(1.0, &shape);
+        let comps = i.prepare_computations(&r, &Intersections::new(vec![i]));
+
+        assert_eq!(vector(1.0, 0.0, 0.0), comps.normalv);
+        assert_eq!(point(0.3, EPSILON, 0.6), comps.over_point);
+        assert!(!comps.inside);
+    }
+
+    #[test]
+    fn precomputing_reflection_vector() {
+        let shape = Shape::plane();
+        let v = (2.0 as FP).sqrt() / 2.0;
+        let r = Ray::new(point(0.0, 1.0, -1.0), vector(0.0, -v, v));
+        let i = Intersection::new((2.0 as FP).sqrt(), &shape);
+        let comps = i.prepare_computations(&r, &Intersections::new(vec![i]));
+
+        assert_eq!(vector(0.0, v, v), comps.reflectv);
+    }
+
+    #[test]
+    fn glossy_reflections_scatter_around_mirror_direction() {
+        let shape = Shape::plane();
+        let v = (2.0 as FP).sqrt() / 2.0;
+        let r = Ray::new(point(0.0, 1.0, -1.0), vector(0.0, -v, v));
+        let i = Intersection::new((2.0 as FP).sqrt(), &shape);
+        let comps = i.prepare_computations(&r, &Intersections::new(vec![i]));
+
+        assert_eq!(comps.reflectv, comps.glossy_reflectv(0.0));
+
+        let mut spread = false;
+        for _ in 0..100 {
+            let d = comps.glossy_reflectv(0.3);
+            assert!(d.dot(&comps.normalv) > 0.0);
+            assert!((1.0 - d.magnitude()).abs() < EPSILON);
+            spread |= d != comps.reflectv;
+        }
+        assert!(spread);
+    }
+
+    #[test]
+    fn glossy_refractions_stay_below_surface() {
+        let shape = Shape::glass_sphere();
+        let r = Ray::new(point(0.0, 0.5, -5.0), vector(0.0, 0.0, 1.0));
+        let i = Intersection::new(5.0 - (0.75 as FP).sqrt(), &shape);
+        let comps = i.prepare_computations(&r, &Intersections::new(vec![i]));
+
+        assert_eq!(comps.refractv(), comps.glossy_refractv(0.0));
+        for _ in 0..100 {
+            let d = comps.glossy_refractv(0.5).unwrap();
+            assert!(d.dot(&comps.normalv) < 0.0);
+        }
+    }
+
+    #[test]
+    fn finding_n1_and_n2_at_various_intersections() {
+        let mut a = Shape::glass_sphere();
+        a.set_transform(scaling(2.0, 2.0, 2.0));
+        a.material.refractive_index = 1.5;
+        let mut b = Shape::glass_sphere();
+        b.set_transform(translation(0.0, 0.0, -0.25));
+        b.material.refractive_index = 2.0;
+        let mut c = Shape::glass_sphere();
+        c.set_transform(translation(0.0, 0.0, 0.25));
+        c.material.refractive_index = 2.5;
+
+        let r = Ray::new(point(0.0, 0.0, -4.0), vector(0.0, 0.0, 1.0));
+        let xs = Intersections::new(vec![
+            Intersection::new(2.0, &a),
+            Intersection::new(2.75, &b),
+            Intersection::new(3.25, &c),
+            Intersection::new(4.75, &b),
+            Intersection::new(5.25, &c),
+            Intersection::new(6.0, &a),
+        ]);
+        let cases = [
+            (1.0, 1.5),
+            (1.5, 2.0),
+            (2.0, 2.5),
+            (2.5, 2.5),
+            (2.5, 1.5),
+            (1.5, 1.0),
+        ];
+
+        for (index, (n1, n2)) in cases.iter().enumerate() {
+            let comps = xs[index].prepare_computations(&r, &xs);
+            assert_eq!(*n1, comps.n1);
+            assert_eq!(*n2, comps.n2);
+        }
+    }
+
+    #[test]
+    fn under_point_is_offset_below_surface() {
+        let r = Ray::new(point(0.0, 0.0, -5.0), vector(0.0, 0.0, 1.0));
+        let mut shape = Shape::glass_sphere();
+        shape.set_transform(translation(0.0, 0.0, 1.0));
+        let i = Intersection::new(5.0, &shape);
+        let comps = i.prepare_computations(&r, &Intersections::new(vec![i]));
+
+        assert!(comps.under_point.z > EPSILON / 2.0);
+        assert!(comps.point.z < comps.under_point.z);
+    }
+
+    #[test]
+    fn schlick_under_total_internal_reflection() {
+        let shape = Shape::glass_sphere();
+        let v = (2.0 as FP).sqrt() / 2.0;
+        let r = Ray::new(point(0.0, 0.0, v), vector(0.0, 1.0, 0.0));
+        let xs = Intersections::new(vec![
+            Intersection::new(-v, &shape),
+            Intersection::new(v, &shape),
+        ]);
+        let comps = xs[1].prepare_computations(&r, &xs);
+
+        assert_eq!(1.0, comps.schlick());
+    }
+
+    #[test]
+    fn schlick_with_perpendicular_viewing_angle() {
+        let shape = Shape::glass_sphere();
+        let r = Ray::new(point(0.0, 0.0, 0.0), vector(0.0, 1.0, 0.0));
+        let xs = Intersections::new(vec![
+            Intersection::new(-1.0, &shape),
+            Intersection::new(1.0, &shape),
+        ]);
+        let comps = xs[1].prepare_computations(&r, &xs);
+
+        assert!((0.04 - comps.schlick()).abs() < EPSILON);
+    }
+
+    #[test]
+    fn schlick_with_small_angle_and_n2_greater_than_n1() {
+        let shape = Shape::glass_sphere();
+        let r = Ray::new(point(0.0, 0.99, -2.0), vector(0.0, 0.0, 1.0));
+        let xs = Intersections::new(vec![Intersection::new(1.8589, &shape)]);
+        let comps = xs[0].prepare_computations(&r, &xs);
+
+        assert!((0.48873 - comps.schlick()).abs() < EPSILON);
+    }
+
+    #[test]
+    fn intersection_encapsulates_uv() {
+        let s = Shape::triangle(
+            point(0.0, 1.0, 0.0),
+            point(-1.0, 0.0, 0.0),
+            point(1.0, 0.0, 0.0),
+        );
+        let i = Intersection::new_with_uv(3.5, &s, 0.2, 0.4);
+
+        assert_eq!(0.2, i.u);
+        assert_eq!(0.4, i.v);
+    }
+
+    #[test]
+    fn hit_refers_to_child_of_group() {
+        let mut g = Shape::group(vec![Shape::sphere()]);
+        g.set_transform(translation(0.0, 0.0, 5.0));
+        let r = Ray::new(point(0.0, 0.0, -5.0), vector(0.0, 0.0, 1.0));
+        let hit = g.intersect(&r).hit().unwrap();
+
+        match &g.shape {
+            Shapes::Group(group) => assert!(std::ptr::eq(&group.children[0], hit.obj)),
+            _ => unreachable!(),
+        }
+    }
 }
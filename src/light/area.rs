@@ -0,0 +1,120 @@
+use crate::tuple::*;
+use crate::FP;
+
+// A rectangle of light divided into usteps * vsteps cells, one sample taken per cell
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct AreaLight {
+    pub corner: Tuple,
+    pub uvec: Tuple,
+    pub usteps: usize,
+    pub vvec: Tuple,
+    pub vsteps: usize,
+    pub jitter: bool,
+}
+
+impl AreaLight {
+    pub fn new(
+        corner: Tuple,
+        full_uvec: Tuple,
+        usteps: usize,
+        full_vvec: Tuple,
+        vsteps: usize,
+    ) -> Self {
+        Self {
+            corner,
+            uvec: full_uvec / usteps as FP,
+            usteps,
+            vvec: full_vvec / vsteps as FP,
+            vsteps,
+            jitter: true,
+        }
+    }
+
+    pub fn samples(&self) -> usize {
+        self.usteps * self.vsteps
+    }
+
+    pub fn center(&self) -> Tuple {
+        self.corner + self.uvec * (self.usteps as FP / 2.0) + self.vvec * (self.vsteps as FP / 2.0)
+    }
+
+    // Picks a random point within each cell so that banding in the penumbra turns into noise;
+    // with jitter turned off the centre of each cell is used instead
+    pub fn point_on_light(&self, u: usize, v: usize) -> Tuple {
+        let (ju, jv) = if self.jitter {
+            (rand::random::<FP>(), rand::random::<FP>())
+        } else {
+            (0.5, 0.5)
+        };
+
+        self.corner + self.uvec * (u as FP + ju) + self.vvec * (v as FP + jv)
+    }
+
+    pub fn positions(&self) -> Vec<Tuple> {
+        let mut positions = Vec::with_capacity(self.samples());
+        for v in 0..self.vsteps {
+            for u in 0..self.usteps {
+                positions.push(self.point_on_light(u, v));
+            }
+        }
+        positions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::light::area::*;
+
+    fn test_light() -> AreaLight {
+        let mut l = AreaLight::new(
+            point(0.0, 0.0, 0.0),
+            vector(2.0, 0.0, 0.0),
+            4,
+            vector(0.0, 0.0, 1.0),
+            2,
+        );
+        l.jitter = false;
+        l
+    }
+
+    #[test]
+    fn creating_area_light() {
+        let l = test_light();
+
+        assert_eq!(point(0.0, 0.0, 0.0), l.corner);
+        assert_eq!(vector(0.5, 0.0, 0.0), l.uvec);
+        assert_eq!(4, l.usteps);
+        assert_eq!(vector(0.0, 0.0, 0.5), l.vvec);
+        assert_eq!(2, l.vsteps);
+        assert_eq!(8, l.samples());
+        assert_eq!(point(1.0, 0.0, 0.5), l.center());
+    }
+
+    #[test]
+    fn finding_single_point_on_area_light() {
+        let l = test_light();
+        let cases = [
+            (0, 0, point(0.25, 0.0, 0.25)),
+            (1, 0, point(0.75, 0.0, 0.25)),
+            (0, 1, point(0.25, 0.0, 0.75)),
+            (2, 0, point(1.25, 0.0, 0.25)),
+            (3, 1, point(1.75, 0.0, 0.75)),
+        ];
+
+        for (u, v, result) in cases {
+            assert_eq!(result, l.point_on_light(u, v));
+        }
+    }
+
+    #[test]
+    fn jittered_points_stay_within_their_cell() {
+        let mut l = test_light();
+        l.jitter = true;
+
+        for _ in 0..100 {
+            let p = l.point_on_light(2, 1);
+            assert!((1.0..=1.5).contains(&p.x));
+            assert!((0.5..=1.0).contains(&p.z));
+        }
+    }
+}
@@ -0,0 +1,15 @@
+use crate::tuple::*;
+
+// A light at infinity, such as the sun, whose rays all travel in `direction`
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct DirectionalLight {
+    pub direction: Tuple,
+}
+
+impl DirectionalLight {
+    pub fn new(direction: Tuple) -> Self {
+        Self {
+            direction: direction.normalize(),
+        }
+    }
+}
@@ -0,0 +1,206 @@
+use crate::background::*;
+use crate::canvas::Canvas;
+use crate::color::Color;
+use crate::tuple::*;
+use crate::FP;
+use crate::PI;
+
+// Light arriving from every direction, taken from an equirectangular radiance map. Directions
+// are importance sampled in proportion to how much light each pixel of the map contributes
+#[derive(Debug, PartialEq, Clone)]
+pub struct EnvironmentLight {
+    pub map: Canvas,
+    pub samples: usize,
+    marginal_cdf: Vec<FP>,
+    conditional_cdfs: Vec<Vec<FP>>,
+    total_weight: FP,
+}
+
+impl EnvironmentLight {
+    pub fn new(map: Canvas, samples: usize) -> Self {
+        assert!(
+            map.width > 0 && map.height > 0,
+            "environment map must have at least one pixel"
+        );
+
+        let mut marginal_cdf = Vec::with_capacity(map.height);
+        let mut conditional_cdfs = Vec::with_capacity(map.height);
+        let mut total_weight = 0.0;
+
+        for y in 0..map.height {
+            let mut cdf = Vec::with_capacity(map.width);
+            let mut row_weight = 0.0;
+            for x in 0..map.width {
+                row_weight += pixel_weight(&map, x, y);
+                cdf.push(row_weight);
+            }
+
+            total_weight += row_weight;
+            marginal_cdf.push(total_weight);
+            conditional_cdfs.push(cdf);
+        }
+
+        Self {
+            map,
+            samples,
+            marginal_cdf,
+            conditional_cdfs,
+            total_weight,
+        }
+    }
+
+    // Picks a row by its share of the whole map and then a pixel by its share of that row,
+    // returning a random direction within the chosen pixel
+    pub fn sample_direction(&self) -> Tuple {
+        if self.total_weight <= 0.0 {
+            return uv_to_direction(rand::random(), rand::random());
+        }
+
+        let y = find_in_cdf(&self.marginal_cdf, rand::random::<FP>() * self.total_weight);
+        let row = &self.conditional_cdfs[y];
+        let x = find_in_cdf(row, rand::random::<FP>() * row[row.len() - 1]);
+
+        let u = (x as FP + rand::random::<FP>()) / self.map.width as FP;
+        let v = 1.0 - (y as FP + rand::random::<FP>()) / self.map.height as FP;
+        uv_to_direction(u, v)
+    }
+
+    pub fn directions(&self) -> Vec<Tuple> {
+        (0..self.samples).map(|_| self.sample_direction()).collect()
+    }
+
+    // Probability density, per unit solid angle, of sample_direction choosing `direction`
+    pub fn pdf(&self, direction: &Tuple) -> FP {
+        let (x, y) = self.pixel_of(direction);
+        let latitude = latitude_of_row(&self.map, y);
+        if self.total_weight <= 0.0 {
+            return 1.0 / (4.0 * PI);
+        }
+        if latitude.cos() <= 0.0 {
+            return 0.0;
+        }
+
+        let pixels = (self.map.width * self.map.height) as FP;
+        let pdf_uv = pixel_weight(&self.map, x, y) / self.total_weight * pixels;
+        pdf_uv / (2.0 * PI * PI * latitude.cos())
+    }
+
+    pub fn radiance(&self, direction: &Tuple) -> Color {
+        let (x, y) = self.pixel_of(direction);
+        self.map.pixel_at(x, y)
+    }
+
+    // The Monte Carlo weight of a sample in `direction`: radiance divided by the pdf it was
+    // chosen with, and by pi so a uniformly white map lights a surface like a light of
+    // intensity 1.0 shining straight at it
+    pub fn contribution(&self, direction: &Tuple) -> Color {
+        let pdf = self.pdf(direction);
+        if pdf <= 0.0 {
+            return Color::black();
+        }
+
+        self.radiance(direction) * (1.0 / (PI * pdf))
+    }
+
+    fn pixel_of(&self, direction: &Tuple) -> (usize, usize) {
+        let (u, v) = direction_to_uv(&direction.normalize());
+        self.map.uv_to_pixel(u, v)
+    }
+}
+
+pub fn luminance(c: &Color) -> FP {
+    0.2126 * c.red + 0.7152 * c.green + 0.0722 * c.blue
+}
+
+fn latitude_of_row(map: &Canvas, y: usize) -> FP {
+    let v = 1.0 - (y as FP + 0.5) / map.height as FP;
+    (v - 0.5) * PI
+}
+
+// Rows near the poles cover less solid angle, so their pixels are weighted down accordingly
+fn pixel_weight(map: &Canvas, x: usize, y: usize) -> FP {
+    luminance(&map.pixel_at(x, y)).max(0.0) * latitude_of_row(map, y).cos()
+}
+
+fn find_in_cdf(cdf: &[FP], value: FP) -> usize {
+    cdf.partition_point(|&c| c <= value).min(cdf.len() - 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::light::environment::*;
+
+    fn map_with_bright_pixel() -> Canvas {
+        let mut map = Canvas::new(8, 4);
+        map.write_pixel(2, 1, Color::new(100.0, 100.0, 100.0));
+        map
+    }
+
+    #[test]
+    fn luminance_of_colors() {
+        assert!((1.0 - luminance(&Color::white())).abs() < crate::EPSILON);
+        assert_eq!(0.0, luminance(&Color::black()));
+        assert!(luminance(&Color::green()) > luminance(&Color::red()));
+    }
+
+    #[test]
+    fn finding_values_in_cdf() {
+        let cdf = [1.0, 1.0, 3.0, 6.0];
+
+        assert_eq!(0, find_in_cdf(&cdf, 0.5));
+        assert_eq!(2, find_in_cdf(&cdf, 1.0));
+        assert_eq!(3, find_in_cdf(&cdf, 5.9));
+    }
+
+    #[test]
+    fn samples_only_come_from_bright_pixels() {
+        let l = EnvironmentLight::new(map_with_bright_pixel(), 64);
+
+        for d in l.directions() {
+            assert_eq!((2, 1), l.pixel_of(&d));
+        }
+    }
+
+    #[test]
+    fn light_matches_background_seen_in_same_direction() {
+        let mut map = Canvas::new(8, 4);
+        for y in 0..4 {
+            for x in 0..8 {
+                map.write_pixel(x, y, Color::new(x as FP, y as FP, 1.0));
+            }
+        }
+        let l = EnvironmentLight::new(map.clone(), 16);
+        let background = Background::Equirectangular(map);
+
+        for i in 0..200 {
+            let d = vector((i as FP * 0.37).sin(), (i as FP * 0.11).cos(), (i as FP * 0.53).sin());
+            assert_eq!(background.color_at(&d), l.radiance(&d));
+        }
+    }
+
+    #[test]
+    fn pdf_is_zero_where_map_is_black() {
+        let l = EnvironmentLight::new(map_with_bright_pixel(), 64);
+
+        assert_eq!(0.0, l.pdf(&vector(0.0, -1.0, 0.2)));
+        assert_eq!(Color::black(), l.contribution(&vector(0.0, -1.0, 0.2)));
+    }
+
+    #[test]
+    #[should_panic(expected = "environment map must have at least one pixel")]
+    fn empty_map_is_rejected() {
+        EnvironmentLight::new(Canvas::new(0, 0), 16);
+    }
+
+    #[test]
+    fn uniform_map_has_uniform_pdf() {
+        let mut map = Canvas::new(64, 32);
+        map.fill(Color::white());
+        let l = EnvironmentLight::new(map, 64);
+
+        for d in [vector(0.0, 0.0, -1.0), vector(0.3, 0.5, 0.8)] {
+            let pdf = l.pdf(&d);
+            assert!((1.0 / (4.0 * PI) - pdf).abs() < 0.001);
+        }
+    }
+}
@@ -0,0 +1,257 @@
+mod area;
+mod directional;
+mod environment;
+mod point;
+mod spot;
+
+use crate::canvas::Canvas;
+use crate::color::Color;
+use crate::light::area::AreaLight;
+use crate::light::directional::DirectionalLight;
+use crate::light::environment::EnvironmentLight;
+pub use crate::light::environment::luminance;
+use crate::light::point::PointLight;
+use crate::light::spot::SpotLight;
+use crate::tuple::*;
+use crate::FP;
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Light {
+    pub light: Lights,
+    pub intensity: Color,
+    pub attenuation: Attenuation,
+}
+
+impl Light {
+    pub fn new(light: Lights, intensity: Color) -> Self {
+        Self {
+            light,
+            intensity,
+            attenuation: Attenuation::None,
+        }
+    }
+
+    pub fn point(position: Tuple, intensity: Color) -> Self {
+        Self::new(Lights::Point(PointLight::new(position)), intensity)
+    }
+
+    pub fn area(
+        corner: Tuple,
+        full_uvec: Tuple,
+        usteps: usize,
+        full_vvec: Tuple,
+        vsteps: usize,
+        intensity: Color,
+    ) -> Self {
+        Self::new(
+            Lights::Area(AreaLight::new(corner, full_uvec, usteps, full_vvec, vsteps)),
+            intensity,
+        )
+    }
+
+    pub fn spot(
+        position: Tuple,
+        direction: Tuple,
+        inner_angle: FP,
+        outer_angle: FP,
+        intensity: Color,
+    ) -> Self {
+        Self::new(
+            Lights::Spot(SpotLight::new(position, direction, inner_angle, outer_angle)),
+            intensity,
+        )
+    }
+
+    pub fn directional(direction: Tuple, intensity: Color) -> Self {
+        Self::new(
+            Lights::Directional(DirectionalLight::new(direction)),
+            intensity,
+        )
+    }
+
+    // An equirectangular radiance map, such as one read with Canvas::read_hdr, lighting the
+    // scene from every direction using `samples` directions per shading point. Panics if the
+    // map has no pixels
+    pub fn environment(map: Canvas, samples: usize, intensity: Color) -> Self {
+        Self::new(
+            Lights::Environment(EnvironmentLight::new(map, samples)),
+            intensity,
+        )
+    }
+
+    // The direction towards each point sampled on the light as seen from `p`, along with how
+    // far away that point is, so shading and shadow rays agree on where the light is. A
+    // directional light is infinitely far away in the same direction from everywhere
+    pub fn samples(&self, p: &Tuple) -> Vec<(Tuple, FP)> {
+        let positions = match &self.light {
+            Lights::Point(l) => vec![l.position],
+            Lights::Area(l) => l.positions(),
+            Lights::Spot(l) => vec![l.position],
+            Lights::Directional(l) => return vec![(-l.direction, FP::INFINITY)],
+            Lights::Environment(l) => {
+                return l
+                    .directions()
+                    .into_iter()
+                    .map(|d| (d, FP::INFINITY))
+                    .collect()
+            }
+        };
+
+        positions
+            .iter()
+            .map(|pos| {
+                let v = *pos - *p;
+                (v.normalize(), v.magnitude())
+            })
+            .collect()
+    }
+
+    // The light arriving from a sample in direction `lightv` that is `distance` away, after
+    // any spot cone and attenuation have been taken into account
+    pub fn intensity_from(&self, lightv: &Tuple, distance: FP) -> Color {
+        let intensity = self.intensity * self.attenuation.factor(distance);
+        match &self.light {
+            Lights::Spot(l) => intensity * l.falloff(&-*lightv),
+            Lights::Environment(l) => intensity * l.contribution(lightv),
+            _ => intensity,
+        }
+    }
+}
+
+// How a light dims with distance. Each falloff is relative to a reference radius, within
+// which the light keeps its full intensity, so scaling a scene and its radii together leaves
+// the lighting unchanged
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Attenuation {
+    None,
+    Linear(FP),
+    InverseSquare(FP),
+}
+
+impl Attenuation {
+    pub fn factor(&self, distance: FP) -> FP {
+        // Directional lights are infinitely far away and never attenuate
+        if !distance.is_finite() {
+            return 1.0;
+        }
+
+        match self {
+            Attenuation::None => 1.0,
+            Attenuation::Linear(radius) => radius / distance.max(*radius),
+            Attenuation::InverseSquare(radius) => (radius / distance.max(*radius)).powi(2),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Lights {
+    Point(PointLight),
+    Area(AreaLight),
+    Spot(SpotLight),
+    Directional(DirectionalLight),
+    Environment(EnvironmentLight),
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::light::*;
+
+    #[test]
+    fn point_light_has_position_and_intensity() {
+        let i = Color::new(1.0, 1.0, 1.0);
+        let p = point(0.0, 0.0, 0.0);
+        let l = Light::point(p, i);
+
+        assert_eq!(Lights::Point(PointLight::new(p)), l.light);
+        assert_eq!(i, l.intensity);
+    }
+
+    #[test]
+    fn point_light_has_single_sample() {
+        let l = Light::point(point(0.0, 0.0, -10.0), Color::white());
+        let samples = l.samples(&point(0.0, 0.0, 0.0));
+
+        assert_eq!(vec![(vector(0.0, 0.0, -1.0), 10.0)], samples);
+    }
+
+    #[test]
+    fn area_light_samples_every_cell() {
+        let l = Light::area(
+            point(0.0, 0.0, 0.0),
+            vector(2.0, 0.0, 0.0),
+            4,
+            vector(0.0, 0.0, 1.0),
+            2,
+            Color::white(),
+        );
+
+        assert_eq!(8, l.samples(&point(0.0, 5.0, 0.0)).len());
+    }
+
+    #[test]
+    fn directional_light_sample_is_constant_and_unbounded() {
+        let l = Light::directional(vector(0.0, -2.0, 0.0), Color::white());
+
+        for p in [point(0.0, 0.0, 0.0), point(100.0, -50.0, 3.0)] {
+            assert_eq!(vec![(vector(0.0, 1.0, 0.0), FP::INFINITY)], l.samples(&p));
+        }
+    }
+
+    #[test]
+    fn lights_do_not_attenuate_by_default() {
+        let l = Light::point(point(0.0, 0.0, 0.0), Color::white());
+
+        assert_eq!(Attenuation::None, l.attenuation);
+        assert_eq!(Color::white(), l.intensity_from(&vector(0.0, 1.0, 0.0), 1000.0));
+    }
+
+    #[test]
+    fn linear_attenuation() {
+        let a = Attenuation::Linear(2.0);
+
+        assert_eq!(1.0, a.factor(1.0));
+        assert_eq!(1.0, a.factor(2.0));
+        assert_eq!(0.5, a.factor(4.0));
+        assert_eq!(0.25, a.factor(8.0));
+    }
+
+    #[test]
+    fn inverse_square_attenuation() {
+        let a = Attenuation::InverseSquare(2.0);
+
+        assert_eq!(1.0, a.factor(1.0));
+        assert_eq!(1.0, a.factor(2.0));
+        assert_eq!(0.25, a.factor(4.0));
+        assert_eq!(0.0625, a.factor(8.0));
+    }
+
+    #[test]
+    fn attenuation_is_unchanged_when_scene_is_rescaled() {
+        let a = Attenuation::InverseSquare(2.0);
+        let b = Attenuation::InverseSquare(20.0);
+
+        assert_eq!(a.factor(7.0), b.factor(70.0));
+    }
+
+    #[test]
+    fn directional_lights_never_attenuate() {
+        let mut l = Light::directional(vector(0.0, -1.0, 0.0), Color::white());
+        l.attenuation = Attenuation::InverseSquare(1.0);
+
+        assert_eq!(
+            Color::white(),
+            l.intensity_from(&vector(0.0, 1.0, 0.0), FP::INFINITY)
+        );
+    }
+
+    #[test]
+    fn environment_light_samples_are_unbounded() {
+        let mut map = Canvas::new(4, 2);
+        map.fill(Color::white());
+        let l = Light::environment(map, 16, Color::white());
+        let samples = l.samples(&point(0.0, 0.0, 0.0));
+
+        assert_eq!(16, samples.len());
+        assert!(samples.iter().all(|(_, distance)| *distance == FP::INFINITY));
+    }
+}
@@ -0,0 +1,12 @@
+use crate::tuple::*;
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct PointLight {
+    pub position: Tuple,
+}
+
+impl PointLight {
+    pub fn new(position: Tuple) -> Self {
+        Self { position }
+    }
+}
@@ -0,0 +1,85 @@
+use crate::tuple::*;
+use crate::FP;
+
+// Inner and outer angles are measured from the spot's direction to the edge of each cone
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct SpotLight {
+    pub position: Tuple,
+    pub direction: Tuple,
+    pub inner_angle: FP,
+    pub outer_angle: FP,
+}
+
+impl SpotLight {
+    pub fn new(position: Tuple, direction: Tuple, inner_angle: FP, outer_angle: FP) -> Self {
+        Self {
+            position,
+            direction: direction.normalize(),
+            inner_angle,
+            outer_angle,
+        }
+    }
+
+    // Full strength inside the inner cone, nothing outside the outer one, and a smoothstep
+    // between the two so the edge of the pool of light isn't a hard line
+    pub fn falloff(&self, towards: &Tuple) -> FP {
+        let cos_angle = self.direction.dot(&towards.normalize());
+        let cos_inner = self.inner_angle.cos();
+        let cos_outer = self.outer_angle.cos();
+
+        if cos_angle >= cos_inner {
+            return 1.0;
+        }
+        if cos_angle <= cos_outer {
+            return 0.0;
+        }
+
+        let t = (cos_angle - cos_outer) / (cos_inner - cos_outer);
+        t * t * (3.0 - 2.0 * t)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::light::spot::*;
+    use crate::PI;
+
+    fn test_spot() -> SpotLight {
+        SpotLight::new(
+            point(0.0, 10.0, 0.0),
+            vector(0.0, -2.0, 0.0),
+            PI / 8.0,
+            PI / 4.0,
+        )
+    }
+
+    #[test]
+    fn creating_spot_light_normalizes_direction() {
+        let l = test_spot();
+        assert_eq!(vector(0.0, -1.0, 0.0), l.direction);
+    }
+
+    #[test]
+    fn full_intensity_inside_inner_cone() {
+        let l = test_spot();
+        assert_eq!(1.0, l.falloff(&vector(0.0, -1.0, 0.0)));
+        assert_eq!(1.0, l.falloff(&vector(0.3, -1.0, 0.0)));
+    }
+
+    #[test]
+    fn no_intensity_outside_outer_cone() {
+        let l = test_spot();
+        assert_eq!(0.0, l.falloff(&vector(1.0, -0.9, 0.0)));
+        assert_eq!(0.0, l.falloff(&vector(0.0, 1.0, 0.0)));
+    }
+
+    #[test]
+    fn intensity_falls_off_smoothly_between_cones() {
+        let l = test_spot();
+        let angle = 3.0 * PI / 16.0;
+        let f = l.falloff(&vector(angle.sin(), -angle.cos(), 0.0));
+
+        assert!(0.0 < f && f < 1.0);
+        assert!(f < l.falloff(&vector(0.5, -1.0, 0.0)));
+    }
+}
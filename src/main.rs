@@ -1,3 +1,11 @@
+#![allow(dead_code)]
+#![allow(clippy::approx_constant)]
+
+mod background;
+mod bounds;
+mod brdf;
+mod bump;
+mod bvh;
 mod camera;
 mod canvas;
 mod color;
@@ -7,6 +15,7 @@ mod material;
 mod matrix;
 mod pattern;
 mod ray;
+mod sampling;
 mod shape;
 mod tuple;
 mod world;
@@ -17,7 +26,7 @@ const EPSILON: FP = 0.00001;
 use crate::camera::Camera;
 use crate::color::Color;
 use crate::pattern::*;
-use crate::light::PointLight;
+use crate::light::Light;
 use crate::matrix::*;
 use crate::shape::Shape;
 use crate::tuple::*;
@@ -31,7 +40,7 @@ fn main() -> std::io::Result<()> {
     let mut world = World::new();
 
     let mut p1 = Pattern::stripe(Color::white(), Color::new(0.0, 0.3, 0.6));
-    p1.transform = rotation_y(PI / 2.0);
+    p1.set_transform(rotation_y(PI / 2.0));
 
     let floor_pattern = Pattern::blended(
         Pattern::stripe(Color::white(), Color::new(0.0, 0.2, 0.6)),
@@ -43,36 +52,36 @@ fn main() -> std::io::Result<()> {
     floor.material.pattern = floor_pattern;
     floor.material.specular = 0.0;
 
-    world.objects.push(floor.clone());
+    world.add(floor.clone());
 
     let mut middle = Shape::sphere();
-    middle.transform = translation(-0.5, 1.0, 0.5);
+    middle.set_transform(translation(-0.5, 1.0, 0.5));
     middle.material.pattern = Pattern::radialgradient(Color::white(), Color::new(0.5, 0.0, 0.5));
-    middle.material.pattern.transform = scaling(0.1, 0.1, 0.1) * rotation_x(PI / 2.0);
+    middle.material.pattern.set_transform(scaling(0.1, 0.1, 0.1) * rotation_x(PI / 2.0));
     middle.material.diffuse = 0.6;
     middle.material.specular = 0.7;
 
-    world.objects.push(middle);
+    world.add(middle);
 
     let mut right = Shape::sphere();
-    right.transform = translation(1.5, 0.5, -0.5) * scaling(0.5, 0.5, 0.5);
+    right.set_transform(translation(1.5, 0.5, -0.5) * scaling(0.5, 0.5, 0.5));
     right.material.pattern = Pattern::checkers(Color::new(0.1, 1.0, 0.5), Color::new(1.0, 0.0, 0.5));
-    right.material.pattern.transform = scaling(0.2, 0.2, 0.2);
+    right.material.pattern.set_transform(scaling(0.2, 0.2, 0.2));
     right.material.diffuse = 0.7;
     right.material.specular = 0.3;
 
-    world.objects.push(right);
+    world.add(right);
 
     let mut left = Shape::sphere();
-    left.transform = translation(-1.5, 0.33, -0.75) * scaling(0.33, 0.33, 0.33);
+    left.set_transform(translation(-1.5, 0.33, -0.75) * scaling(0.33, 0.33, 0.33));
     left.material.pattern = Pattern::ring(Color::new(1.0, 0.8, 0.1), Color::white());
-    left.material.pattern.transform = scaling(0.1, 0.1, 0.1) * rotation_x(PI / 2.0);
+    left.material.pattern.set_transform(scaling(0.1, 0.1, 0.1) * rotation_x(PI / 2.0));
     left.material.diffuse = 0.7;
     left.material.specular = 0.3;
 
-    world.objects.push(left);
+    world.add(left);
 
-    world.light = Some(PointLight::new(
+    world.lights.push(Light::point(
         point(-10.0, 10.0, -10.0),
         Color::new(1.0, 1.0, 1.0),
     ));
@@ -84,6 +93,7 @@ fn main() -> std::io::Result<()> {
         vector(0.0, 1.0, 0.0),
     );
 
+    world.build_bvh();
     let canvas = camera.render(&world);
 
     let mut file = File::create("output.ppm")?;
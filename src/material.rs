@@ -1,9 +1,33 @@
+use crate::brdf::*;
+use crate::bump::Bump;
 use crate::color::Color;
-use crate::light::PointLight;
+use crate::light::{luminance, Light};
 use crate::pattern::Pattern;
 use crate::FP;
 use crate::*;
 
+// Phong is driven by ambient, diffuse, specular and shininess, while Cook-Torrance takes the
+// pattern as its base color and is driven by metallic and roughness instead
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum ShadingModel {
+    Phong,
+    CookTorrance,
+}
+
+// The scalar parameters of a material that a pattern can be painted onto
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Parameter {
+    Ambient,
+    Diffuse,
+    Specular,
+    Shininess,
+    Reflective,
+    Transparency,
+    RefractiveIndex,
+    Metallic,
+    Roughness,
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub struct Material {
     pub pattern: Pattern,
@@ -11,6 +35,19 @@ pub struct Material {
     pub diffuse: FP,
     pub specular: FP,
     pub shininess: FP,
+    pub reflective: FP,
+    pub transparency: FP,
+    pub refractive_index: FP,
+    pub emissive: Color,
+    pub model: ShadingModel,
+    pub metallic: FP,
+    // Also blurs reflections and refractions under either model, each averaged over
+    // glossy_samples rays
+    pub roughness: FP,
+    pub glossy_samples: usize,
+    pub bump: Option<Bump>,
+    // Each scales its parameter by the brightness of the pattern at the point being shaded
+    pub maps: Vec<(Parameter, Pattern)>,
 }
 
 impl Material {
@@ -21,52 +58,237 @@ impl Material {
             diffuse: 0.9,
             specular: 0.9,
             shininess: 200.0,
+            reflective: 0.0,
+            transparency: 0.0,
+            refractive_index: 1.0,
+            emissive: Color::black(),
+            model: ShadingModel::Phong,
+            metallic: 0.0,
+            roughness: 0.0,
+            glossy_samples: 16,
+            bump: None,
+            maps: vec![],
+        }
+    }
+
+    pub fn cook_torrance() -> Self {
+        Self {
+            model: ShadingModel::CookTorrance,
+            roughness: 0.5,
+            ..Self::new()
+        }
+    }
+
+    // The model and parameters the material shades with, ignoring any maps
+    pub fn shading(&self) -> Shading {
+        Shading {
+            model: self.model,
+            ambient: self.ambient,
+            diffuse: self.diffuse,
+            specular: self.specular,
+            shininess: self.shininess,
+            reflective: self.reflective,
+            transparency: self.transparency,
+            refractive_index: self.refractive_index,
+            metallic: self.metallic,
+            roughness: self.roughness,
         }
     }
 
+    // The material's shading at `point`, with every mapped parameter worked out
+    pub fn at(&self, obj: &Shape, point: &Tuple) -> Shading {
+        let mut shading = self.shading();
+
+        for (parameter, pattern) in self.maps.iter() {
+            shading.scale(*parameter, luminance(&pattern.color_at_object(obj, point)));
+        }
+
+        shading
+    }
+
+    // Just the one parameter at `point`, leaving the maps of every other one alone
+    pub fn parameter_at(&self, parameter: Parameter, obj: &Shape, point: &Tuple) -> FP {
+        let mut shading = self.shading();
+
+        for (_, pattern) in self.maps.iter().filter(|(p, _)| *p == parameter) {
+            shading.scale(parameter, luminance(&pattern.color_at_object(obj, point)));
+        }
+
+        *shading.parameter_mut(parameter)
+    }
+
     pub fn lighting(
         &self,
         obj: &Shape,
-        light: PointLight,
+        light: &Light,
         point: Tuple,
         eyev: Tuple,
         normalv: Tuple,
-        in_shadow: bool,
+        intensity: FP,
     ) -> Color {
-        let effective_color = self.pattern.color_at_object(obj, &point) * light.intensity;
-        let lightv = (light.position - point).normalize();
-        let ambient = effective_color * self.ambient;
+        let samples: Vec<_> = light
+            .samples(&point)
+            .into_iter()
+            .map(|(lightv, distance)| (lightv, distance, intensity))
+            .collect();
+        let color = self.pattern.color_at_object(obj, &point);
+        self.at(obj, &point).lighting(color, light, eyev, normalv, &samples)
+    }
+}
 
-        if in_shadow {
-            return ambient;
+// How a material shades a single point: its model and its scalar parameters, once any maps
+// have been worked out there. Unlike the material it's cheap to copy, as it has no patterns
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct Shading {
+    pub model: ShadingModel,
+    pub ambient: FP,
+    pub diffuse: FP,
+    pub specular: FP,
+    pub shininess: FP,
+    pub reflective: FP,
+    pub transparency: FP,
+    pub refractive_index: FP,
+    pub metallic: FP,
+    pub roughness: FP,
+}
+
+impl Shading {
+    fn parameter_mut(&mut self, parameter: Parameter) -> &mut FP {
+        match parameter {
+            Parameter::Ambient => &mut self.ambient,
+            Parameter::Diffuse => &mut self.diffuse,
+            Parameter::Specular => &mut self.specular,
+            Parameter::Shininess => &mut self.shininess,
+            Parameter::Reflective => &mut self.reflective,
+            Parameter::Transparency => &mut self.transparency,
+            Parameter::RefractiveIndex => &mut self.refractive_index,
+            Parameter::Metallic => &mut self.metallic,
+            Parameter::Roughness => &mut self.roughness,
         }
+    }
+
+    // A dark map would otherwise take a refractive index below that of a vacuum, where
+    // refraction breaks down, or shininess to 0, where the highlight covers everything
+    fn scale(&mut self, parameter: Parameter, by: FP) {
+        let value = self.parameter_mut(parameter);
+        *value *= by;
+
+        if matches!(parameter, Parameter::RefractiveIndex | Parameter::Shininess) {
+            *value = value.max(1.0);
+        }
+    }
+
+    // Lights a surface of the given color with samples already taken on the light, each with
+    // how much of it is visible from the point, so that the same samples can be used for
+    // shading and shadows
+    pub fn lighting(
+        &self,
+        color: Color,
+        light: &Light,
+        eyev: Tuple,
+        normalv: Tuple,
+        samples: &[(Tuple, FP, FP)],
+    ) -> Color {
+        let ambient = color * light.intensity * self.ambient;
+
+        // Diffuse and specular are averaged over every sample on the light, visible or not
+        let mut sum = Color::black();
 
-        let light_dot_normal = lightv.dot(&normalv);
-        let diffuse: Color;
-        let specular: Color;
+        for (lightv, distance, visibility) in samples.iter() {
+            let light_dot_normal = lightv.dot(&normalv);
+            if *visibility == 0.0 || light_dot_normal < 0.0 {
+                continue;
+            }
+
+            let light_color = light.intensity_from(lightv, *distance) * *visibility;
+            if self.model == ShadingModel::CookTorrance {
+                sum = sum + light_color * self.reflectance(color, &eyev, &normalv, lightv);
+                continue;
+            }
 
-        if light_dot_normal < 0.0 {
-            diffuse = Color::black();
-            specular = Color::black();
-        } else {
-            diffuse = effective_color * self.diffuse * light_dot_normal;
+            let diffuse = color * light_color * self.diffuse * light_dot_normal;
 
-            let reflectv = (-lightv).reflect(&normalv);
+            let reflectv = (-*lightv).reflect(&normalv);
             let reflect_dot_eye = reflectv.dot(&eyev);
-            if reflect_dot_eye < 0.0 {
-                specular = Color::black();
+            let specular = if reflect_dot_eye < 0.0 {
+                Color::black()
             } else {
                 let factor = reflect_dot_eye.powf(self.shininess);
-                specular = light.intensity * self.specular * factor;
-            }
+                light_color * self.specular * factor
+            };
+
+            sum = sum + diffuse + specular;
         }
 
-        return ambient + diffuse + specular;
+        if samples.is_empty() {
+            return ambient;
+        }
+        ambient + sum * (1.0 / samples.len() as FP)
+    }
+
+    // Metals reflect in their own color, and everything else reflects a few percent of white
+    pub fn specular_f0(&self, color: Color) -> Color {
+        Color::white() * (DIELECTRIC_F0 * (1.0 - self.metallic)) + color * self.metallic
+    }
+
+    // The Cook-Torrance BRDF times the cosine of the light's angle, for light arriving along
+    // `lightv`. It's scaled by pi to match the Phong model, where a white surface facing a
+    // light of intensity 1.0 is lit fully rather than by 1/pi
+    pub fn reflectance(
+        &self,
+        color: Color,
+        eyev: &Tuple,
+        normalv: &Tuple,
+        lightv: &Tuple,
+    ) -> Color {
+        let (diffuse, specular) = self.reflectance_lobes(color, eyev, normalv, lightv);
+        diffuse + specular
+    }
+
+    // The diffuse and glossy parts of reflectance, kept apart for the path tracer, which
+    // finds light through each of them differently
+    pub fn reflectance_lobes(
+        &self,
+        color: Color,
+        eyev: &Tuple,
+        normalv: &Tuple,
+        lightv: &Tuple,
+    ) -> (Color, Color) {
+        let n_dot_l = lightv.dot(normalv);
+        let n_dot_v = eyev.dot(normalv);
+        if n_dot_l <= 0.0 || n_dot_v <= 0.0 {
+            return (Color::black(), Color::black());
+        }
+
+        let halfv = (*lightv + *eyev).normalize();
+        let alpha = alpha(self.roughness);
+        let fresnel = fresnel_schlick(self.specular_f0(color), eyev.dot(&halfv));
+
+        let specular = fresnel
+            * (ggx_distribution(normalv.dot(&halfv), alpha)
+                * smith_geometry(n_dot_v, n_dot_l, alpha)
+                / (4.0 * n_dot_v));
+        let diffuse = (Color::white() - fresnel) * color * ((1.0 - self.metallic) / PI);
+
+        (diffuse * (PI * n_dot_l), specular * (PI * n_dot_l))
+    }
+
+    // How strongly the surface mirrors what's around it towards the eye. Phong materials only
+    // reflect when told to, while Cook-Torrance ones always reflect as much as their lobe does
+    pub fn reflection_weight(&self, color: Color, eyev: &Tuple, normalv: &Tuple) -> Color {
+        match self.model {
+            ShadingModel::Phong => Color::white() * self.reflective,
+            ShadingModel::CookTorrance => {
+                specular_albedo(self.specular_f0(color), self.roughness, eyev.dot(normalv))
+            }
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::light::{Attenuation, Lights};
+    use crate::canvas::Canvas;
     use crate::material::*;
 
     #[test]
@@ -78,6 +300,16 @@ mod tests {
         assert_eq!(m.diffuse, 0.9);
         assert_eq!(m.specular, 0.9);
         assert_eq!(m.shininess, 200.0);
+        assert_eq!(m.reflective, 0.0);
+        assert_eq!(m.transparency, 0.0);
+        assert_eq!(m.refractive_index, 1.0);
+        assert_eq!(m.emissive, Color::black());
+        assert_eq!(m.model, ShadingModel::Phong);
+        assert_eq!(m.metallic, 0.0);
+        assert_eq!(m.roughness, 0.0);
+        assert_eq!(m.glossy_samples, 16);
+        assert_eq!(m.bump, None);
+        assert!(m.maps.is_empty());
     }
 
     #[test]
@@ -87,11 +319,11 @@ mod tests {
 
         let eyev = vector(0.0, 0.0, -1.0);
         let normalv = vector(0.0, 0.0, -1.0);
-        let light = PointLight::new(point(0.0, 0.0, -10.0), Color::new(1.0, 1.0, 1.0));
+        let light = Light::point(point(0.0, 0.0, -10.0), Color::new(1.0, 1.0, 1.0));
 
         assert_eq!(
             Color::new(1.9, 1.9, 1.9),
-            m.lighting(&Shape::sphere(), light, p, eyev, normalv, false)
+            m.lighting(&Shape::sphere(), &light, p, eyev, normalv, 1.0)
         );
     }
 
@@ -103,11 +335,11 @@ mod tests {
         let num = (2.0 as FP).sqrt() / 2.0;
         let eyev = vector(0.0, num, -num);
         let normalv = vector(0.0, 0.0, -1.0);
-        let light = PointLight::new(point(0.0, 0.0, -10.0), Color::new(1.0, 1.0, 1.0));
+        let light = Light::point(point(0.0, 0.0, -10.0), Color::new(1.0, 1.0, 1.0));
 
         assert_eq!(
             Color::new(1.0, 1.0, 1.0),
-            m.lighting(&Shape::sphere(), light, p, eyev, normalv, false)
+            m.lighting(&Shape::sphere(), &light, p, eyev, normalv, 1.0)
         );
     }
 
@@ -118,12 +350,12 @@ mod tests {
 
         let eyev = vector(0.0, 0.0, -1.0);
         let normalv = vector(0.0, 0.0, -1.0);
-        let light = PointLight::new(point(0.0, 10.0, -10.0), Color::new(1.0, 1.0, 1.0));
+        let light = Light::point(point(0.0, 10.0, -10.0), Color::new(1.0, 1.0, 1.0));
 
         let num: FP = 0.736396;
         assert_eq!(
             Color::new(num, num, num),
-            m.lighting(&Shape::sphere(), light, p, eyev, normalv, false)
+            m.lighting(&Shape::sphere(), &light, p, eyev, normalv, 1.0)
         );
     }
 
@@ -136,11 +368,11 @@ mod tests {
 
         let eyev = vector(0.0, -num, -num);
         let normalv = vector(0.0, 0.0, -1.0);
-        let light = PointLight::new(point(0.0, 10.0, -10.0), Color::white());
+        let light = Light::point(point(0.0, 10.0, -10.0), Color::white());
 
         assert_eq!(
             Color::new(1.6364, 1.6364, 1.6364),
-            m.lighting(&Shape::sphere(), light, p, eyev, normalv, false)
+            m.lighting(&Shape::sphere(), &light, p, eyev, normalv, 1.0)
         );
     }
 
@@ -151,11 +383,11 @@ mod tests {
 
         let eyev = vector(0.0, 0.0, -1.0);
         let normalv = vector(0.0, 0.0, -1.0);
-        let light = PointLight::new(point(0.0, 0.0, -10.0), Color::white());
+        let light = Light::point(point(0.0, 0.0, -10.0), Color::white());
 
         assert_eq!(
             Color::new(0.1, 0.1, 0.1),
-            m.lighting(&Shape::sphere(), light, p, eyev, normalv, true)
+            m.lighting(&Shape::sphere(), &light, p, eyev, normalv, 0.0)
         );
     }
 
@@ -169,29 +401,277 @@ mod tests {
 
         let eyev = vector(0.0, 0.0, -1.0);
         let normalv = vector(0.0, 0.0, -1.0);
-        let light = PointLight::new(point(0.0, 0.0, -10.0), Color::white());
+        let light = Light::point(point(0.0, 0.0, -10.0), Color::white());
 
         assert_eq!(
             Color::white(),
             m.lighting(
                 &Shape::sphere(),
-                light,
+                &light,
                 point(0.9, 0.0, 0.0),
                 eyev,
                 normalv,
-                false
+                1.0
             )
         );
         assert_eq!(
             Color::black(),
             m.lighting(
                 &Shape::sphere(),
-                light,
+                &light,
                 point(1.1, 0.0, 0.0),
                 eyev,
                 normalv,
-                false
+                1.0
             )
         );
     }
+
+    #[test]
+    fn lighting_uses_light_intensity_to_attenuate_color() {
+        let mut w = World::default();
+        w.lights = vec![Light::point(point(0.0, 0.0, -10.0), Color::white())];
+        w.objects_mut()[0].material.ambient = 0.1;
+        w.objects_mut()[0].material.diffuse = 0.9;
+        w.objects_mut()[0].material.specular = 0.0;
+        w.objects_mut()[0].material.pattern = Pattern::solid(Color::white());
+
+        let p = point(0.0, 0.0, -1.0);
+        let eyev = vector(0.0, 0.0, -1.0);
+        let normalv = vector(0.0, 0.0, -1.0);
+        let cases = [(1.0, 1.0), (0.5, 0.55), (0.0, 0.1)];
+
+        for (intensity, result) in cases {
+            let s = &w.objects()[0];
+            assert_eq!(
+                Color::new(result, result, result),
+                s.material
+                    .lighting(s, &w.lights[0], p, eyev, normalv, intensity)
+            );
+        }
+    }
+
+    #[test]
+    fn lighting_samples_area_light() {
+        let mut light = Light::area(
+            point(-0.5, -0.5, -5.0),
+            vector(1.0, 0.0, 0.0),
+            2,
+            vector(0.0, 1.0, 0.0),
+            2,
+            Color::white(),
+        );
+        if let Lights::Area(l) = &mut light.light {
+            l.jitter = false;
+        }
+
+        let mut shape = Shape::sphere();
+        shape.material.ambient = 0.1;
+        shape.material.diffuse = 0.9;
+        shape.material.specular = 0.0;
+
+        let eye = point(0.0, 0.0, -5.0);
+        let v = (2.0 as FP).sqrt() / 2.0;
+        let cases = [
+            (point(0.0, 0.0, -1.0), Color::new(0.9965, 0.9965, 0.9965)),
+            (point(0.0, v, -v), Color::new(0.62318, 0.62318, 0.62318)),
+        ];
+
+        for (p, result) in cases {
+            let eyev = (eye - p).normalize();
+            let normalv = vector(p.x, p.y, p.z);
+            assert_eq!(
+                result,
+                shape.material.lighting(&shape, &light, p, eyev, normalv, 1.0)
+            );
+        }
+    }
+
+    #[test]
+    fn lighting_outside_spot_light_cone() {
+        let m = Material::new();
+        let eyev = vector(0.0, 0.0, -1.0);
+        let normalv = vector(0.0, 0.0, -1.0);
+        let light = Light::spot(
+            point(0.0, 0.0, -10.0),
+            vector(0.0, 0.0, 1.0),
+            PI / 16.0,
+            PI / 8.0,
+            Color::white(),
+        );
+
+        assert_eq!(
+            Color::new(1.9, 1.9, 1.9),
+            m.lighting(&Shape::sphere(), &light, point(0.0, 0.0, 0.0), eyev, normalv, 1.0)
+        );
+        assert_eq!(
+            Color::new(0.1, 0.1, 0.1),
+            m.lighting(&Shape::sphere(), &light, point(0.0, 10.0, 0.0), eyev, normalv, 1.0)
+        );
+    }
+
+    #[test]
+    fn lighting_with_attenuated_light() {
+        let mut m = Material::new();
+        m.ambient = 0.0;
+        m.specular = 0.0;
+        m.diffuse = 1.0;
+        let eyev = vector(0.0, 0.0, -1.0);
+        let normalv = vector(0.0, 0.0, -1.0);
+
+        let mut light = Light::point(point(0.0, 0.0, -10.0), Color::white());
+        light.attenuation = Attenuation::InverseSquare(5.0);
+
+        assert_eq!(
+            Color::new(0.25, 0.25, 0.25),
+            m.lighting(&Shape::sphere(), &light, point(0.0, 0.0, 0.0), eyev, normalv, 1.0)
+        );
+    }
+
+    #[test]
+    fn lighting_with_uniform_environment_light() {
+        let mut m = Material::new();
+        m.ambient = 0.0;
+        m.diffuse = 1.0;
+        m.specular = 0.0;
+        let mut map = Canvas::new(32, 16);
+        map.fill(Color::white());
+        let light = Light::environment(map, 4096, Color::white());
+
+        let eyev = vector(0.0, 1.0, 0.0);
+        let normalv = vector(0.0, 1.0, 0.0);
+        let c = m.lighting(&Shape::sphere(), &light, point(0.0, 1.0, 0.0), eyev, normalv, 1.0);
+
+        assert!((1.0 - c.red).abs() < 0.1);
+    }
+
+    #[test]
+    fn cook_torrance_dielectric_facing_light() {
+        let mut m = Material::cook_torrance();
+        m.ambient = 0.0;
+        let light = Light::point(point(0.0, 0.0, -10.0), Color::white());
+        let eyev = vector(0.0, 0.0, -1.0);
+        let normalv = vector(0.0, 0.0, -1.0);
+
+        // Mostly diffuse, with a highlight of F0 * D / 4 on top
+        let c = m.lighting(&Shape::sphere(), &light, origin(), eyev, normalv, 1.0);
+        let specular = DIELECTRIC_F0 * ggx_distribution(1.0, alpha(0.5)) / 4.0 * PI;
+        let expected = (1.0 - DIELECTRIC_F0) + specular;
+        assert!((expected - c.red).abs() < crate::EPSILON);
+    }
+
+    #[test]
+    fn cook_torrance_metal_reflects_in_its_own_color() {
+        let mut m = Material::cook_torrance();
+        m.pattern = Pattern::solid(Color::new(1.0, 0.5, 0.0));
+        m.metallic = 1.0;
+        m.ambient = 0.0;
+        m.roughness = 0.3;
+        let light = Light::point(point(0.0, 0.0, -10.0), Color::white());
+        let eyev = vector(0.0, 0.0, -1.0);
+        let normalv = vector(0.0, 0.0, -1.0);
+
+        let c = m.lighting(&Shape::sphere(), &light, origin(), eyev, normalv, 1.0);
+        assert!((c.green * 2.0 - c.red).abs() < crate::EPSILON);
+        assert_eq!(0.0, c.blue);
+    }
+
+    #[test]
+    fn cook_torrance_highlight_follows_mirror_direction() {
+        let mut m = Material::cook_torrance();
+        m.roughness = 0.2;
+        let normalv = vector(0.0, 1.0, 0.0);
+        let lightv = vector(1.0, 1.0, 0.0).normalize();
+        let color = Color::white();
+
+        let mirrored = vector(-1.0, 1.0, 0.0).normalize();
+        let off_axis = vector(1.0, 1.0, 0.0).normalize();
+
+        let shading = m.shading();
+        let highlight = shading.reflectance(color, &mirrored, &normalv, &lightv);
+        assert!(highlight.red > shading.reflectance(color, &off_axis, &normalv, &lightv).red);
+    }
+
+    #[test]
+    fn cook_torrance_ignores_light_behind_surface() {
+        let m = Material::cook_torrance();
+
+        let c = m.shading().reflectance(
+            Color::white(),
+            &vector(0.0, 1.0, 0.0),
+            &vector(0.0, 1.0, 0.0),
+            &vector(0.0, -1.0, 0.0),
+        );
+        assert_eq!(Color::black(), c);
+    }
+
+    #[test]
+    fn reflection_weight_of_each_model() {
+        let mut m = Material::new();
+        m.reflective = 0.5;
+        let eyev = vector(0.0, 1.0, 0.0);
+        let normalv = vector(0.0, 1.0, 0.0);
+
+        assert_eq!(
+            Color::new(0.5, 0.5, 0.5),
+            m.shading().reflection_weight(Color::white(), &eyev, &normalv)
+        );
+
+        let mut m = Material::cook_torrance();
+        m.metallic = 1.0;
+        m.roughness = 0.0;
+        let w = m.shading().reflection_weight(Color::new(1.0, 0.5, 0.0), &eyev, &normalv);
+        assert!(w.red > 0.95 && w.green > 0.45 && w.green < 0.55);
+    }
+
+    #[test]
+    fn unmapped_material_shades_with_its_own_parameters() {
+        let m = Material::new();
+
+        assert_eq!(m.shading(), m.at(&Shape::sphere(), &point(0.0, 0.0, 0.0)));
+    }
+
+    #[test]
+    fn mapped_parameters_scale_by_pattern_brightness() {
+        let mut m = Material::new();
+        m.specular = 0.8;
+        m.reflective = 0.5;
+        m.maps.push((
+            Parameter::Specular,
+            Pattern::stripe(Color::white(), Color::black()),
+        ));
+        m.maps.push((
+            Parameter::Reflective,
+            Pattern::solid(Color::new(0.5, 0.5, 0.5)),
+        ));
+        let shape = Shape::sphere();
+
+        let wet = m.at(&shape, &point(0.5, 0.0, 0.0));
+        assert_eq!(0.8, wet.specular);
+        assert!((0.25 - wet.reflective).abs() < crate::EPSILON);
+
+        let dry = m.at(&shape, &point(1.5, 0.0, 0.0));
+        assert_eq!(0.0, dry.specular);
+        assert_eq!(m.diffuse, dry.diffuse);
+
+        let dry_specular = m.parameter_at(Parameter::Specular, &shape, &point(1.5, 0.0, 0.0));
+        assert_eq!(0.0, dry_specular);
+        assert_eq!(m.diffuse, m.parameter_at(Parameter::Diffuse, &shape, &point(1.5, 0.0, 0.0)));
+    }
+
+    #[test]
+    fn black_maps_keep_refraction_and_highlights_working() {
+        let mut m = Material::new();
+        m.refractive_index = 1.5;
+        m.maps.push((Parameter::RefractiveIndex, Pattern::solid(Color::black())));
+        m.maps.push((Parameter::Shininess, Pattern::solid(Color::black())));
+        let shading = m.at(&Shape::sphere(), &origin());
+
+        assert_eq!(1.0, shading.refractive_index);
+        assert_eq!(1.0, shading.shininess);
+        assert_eq!(
+            1.0,
+            m.parameter_at(Parameter::RefractiveIndex, &Shape::sphere(), &origin())
+        );
+    }
 }
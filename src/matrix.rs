@@ -30,7 +30,7 @@ impl<const S: usize> Matrix<{ S }> {
     }
 
     pub fn transpose(&self) -> Self {
-        let mut out = self.clone();
+        let mut out = *self;
 
         for r in 0..S {
             for c in 0..S {
@@ -177,7 +177,7 @@ impl Matrix4 {
     fn cofactor(&self, r: usize, c: usize) -> FP {
         let minor = self.minor(r, c);
 
-        return if (r + c) % 2 == 0 { minor } else { -minor };
+        if (r + c).is_multiple_of(2) { minor } else { -minor }
     }
 
     fn determinant(&self) -> FP {
@@ -234,7 +234,7 @@ impl Matrix3 {
     fn cofactor(&self, r: usize, c: usize) -> FP {
         let minor = self.minor(r, c);
 
-        return if (r + c) % 2 == 0 { minor } else { -minor };
+        if (r + c).is_multiple_of(2) { minor } else { -minor }
     }
 
     fn determinant(&self) -> FP {
@@ -262,7 +262,7 @@ impl<const S: usize> ops::Mul<Matrix<S>> for Matrix<S> {
     type Output = Matrix<S>;
 
     fn mul(self, rhs: Matrix<S>) -> Matrix<S> {
-        let mut out = self.clone();
+        let mut out = self;
         for r in 0..S {
             for c in 0..S {
                 out.set(r, c, (0..S).map(|i| self.get(r, i) * rhs.get(i, c)).sum());
@@ -276,7 +276,7 @@ impl<const S: usize> ops::Mul<Tuple> for Matrix<S> {
     type Output = Tuple;
 
     fn mul(self, rhs: Tuple) -> Tuple {
-        let mut out = rhs.clone();
+        let mut out = rhs;
         for r in 0..S {
             out.set(r, (0..S).map(|i| self.get(r, i) * rhs.get(i)).sum());
         }
@@ -1,4 +1,3 @@
-use crate::FP;
 use crate::color::Color;
 use crate::tuple::*;
 
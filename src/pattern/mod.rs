@@ -1,6 +1,8 @@
 mod blended;
 mod checkers;
 mod gradient;
+mod noise;
+mod perturbed;
 mod radialgradient;
 mod ring;
 mod stripe;
@@ -9,17 +11,21 @@ use crate::matrix::*;
 use crate::pattern::blended::Blended;
 use crate::pattern::checkers::Checkers;
 use crate::pattern::gradient::Gradient;
+use crate::pattern::noise::Noise;
+use crate::pattern::perturbed::Perturbed;
 use crate::pattern::radialgradient::RadialGradient;
 use crate::pattern::ring::Ring;
 use crate::pattern::stripe::Stripe;
 use crate::shape::*;
 use crate::Color;
 use crate::Tuple;
+use crate::FP;
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct Pattern {
     pub pattern: Patterns,
-    pub transform: Matrix4,
+    transform: Matrix4,
+    inverse: Matrix4,
 }
 
 impl Pattern {
@@ -27,9 +33,19 @@ impl Pattern {
         Self {
             pattern,
             transform: Matrix4::identity(),
+            inverse: Matrix4::identity(),
         }
     }
 
+    pub fn transform(&self) -> Matrix4 {
+        self.transform
+    }
+
+    pub fn set_transform(&mut self, transform: Matrix4) {
+        self.transform = transform;
+        self.inverse = transform.inverse();
+    }
+
     pub fn blended(a: Pattern, b: Pattern) -> Self {
         Self::new(Patterns::Blended(Blended::new(a, b)))
     }
@@ -42,6 +58,20 @@ impl Pattern {
         Self::new(Patterns::Gradient(Gradient::new(a, b)))
     }
 
+    pub fn noise(a: Color, b: Color, octaves: usize, lacunarity: FP, gain: FP) -> Self {
+        Self::new(Patterns::Noise(Noise::new(a, b, octaves, lacunarity, gain)))
+    }
+
+    pub fn turbulence(a: Color, b: Color, octaves: usize, lacunarity: FP, gain: FP) -> Self {
+        let mut noise = Noise::new(a, b, octaves, lacunarity, gain);
+        noise.turbulence = true;
+        Self::new(Patterns::Noise(noise))
+    }
+
+    pub fn perturbed(pattern: Pattern, scale: FP) -> Self {
+        Self::new(Patterns::Perturbed(Perturbed::new(pattern, scale)))
+    }
+
     pub fn radialgradient(a: Color, b: Color) -> Self {
         Self::new(Patterns::RadialGradient(RadialGradient::new(a, b)))
     }
@@ -59,20 +89,22 @@ impl Pattern {
     }
 
     pub fn color_at(&self, obj_point: &Tuple) -> Color {
-        let t = self.transform.inverse() * *obj_point;
-        match self.pattern.clone() {
+        let t = self.inverse * *obj_point;
+        match &self.pattern {
             Patterns::Blended(p) => p.color_at(&t),
             Patterns::Checkers(p) => p.color_at(&t),
             Patterns::Gradient(p) => p.color_at(&t),
+            Patterns::Noise(p) => p.color_at(&t),
+            Patterns::Perturbed(p) => p.color_at(&t),
             Patterns::RadialGradient(p) => p.color_at(&t),
             Patterns::Ring(p) => p.color_at(&t),
-            Patterns::Solid(c) => c,
+            Patterns::Solid(c) => *c,
             Patterns::Stripe(p) => p.color_at(&t),
         }
     }
 
     pub fn color_at_object(&self, obj: &Shape, p: &Tuple) -> Color {
-        let obj_point = obj.transform.inverse() * *p;
+        let obj_point = obj.world_to_object(*p);
         self.color_at(&obj_point)
     }
 }
@@ -82,6 +114,8 @@ pub enum Patterns {
     Blended(Blended),
     Checkers(Checkers),
     Gradient(Gradient),
+    Noise(Noise),
+    Perturbed(Perturbed),
     RadialGradient(RadialGradient),
     Ring(Ring),
     Solid(Color),
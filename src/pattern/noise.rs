@@ -0,0 +1,206 @@
+use crate::color::Color;
+use crate::tuple::*;
+use crate::FP;
+
+// Smooth random variation between two colors, summed over `octaves` layers of gradient noise
+// (fractional Brownian motion). Each layer is `lacunarity` times finer and `gain` times
+// fainter than the one before; turbulence sums the absolute value of each layer instead,
+// giving sharp creases like those in marble or smoke
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct Noise {
+    pub a: Color,
+    pub b: Color,
+    pub octaves: usize,
+    pub lacunarity: FP,
+    pub gain: FP,
+    pub turbulence: bool,
+}
+
+impl Noise {
+    pub fn new(a: Color, b: Color, octaves: usize, lacunarity: FP, gain: FP) -> Self {
+        Self {
+            a,
+            b,
+            octaves,
+            lacunarity,
+            gain,
+            turbulence: false,
+        }
+    }
+
+    pub fn color_at(&self, p: &Tuple) -> Color {
+        let t = if self.turbulence {
+            turbulence(p, self.octaves, self.lacunarity, self.gain)
+        } else {
+            (fbm(p, self.octaves, self.lacunarity, self.gain) + 1.0) / 2.0
+        };
+
+        self.a + (self.b - self.a) * t.clamp(0.0, 1.0)
+    }
+}
+
+// Sums of layers are divided by the total amplitude so they stay within the range of a
+// single layer
+pub fn fbm(p: &Tuple, octaves: usize, lacunarity: FP, gain: FP) -> FP {
+    octave_sum(p, octaves, lacunarity, gain, perlin)
+}
+
+pub fn turbulence(p: &Tuple, octaves: usize, lacunarity: FP, gain: FP) -> FP {
+    octave_sum(p, octaves, lacunarity, gain, |p| perlin(p).abs())
+}
+
+fn octave_sum(
+    p: &Tuple,
+    octaves: usize,
+    lacunarity: FP,
+    gain: FP,
+    layer: impl Fn(&Tuple) -> FP,
+) -> FP {
+    let mut sum = 0.0;
+    let mut total = 0.0;
+    let mut amplitude = 1.0;
+    let mut frequency = 1.0;
+
+    for _ in 0..octaves.max(1) {
+        sum += layer(&(*p * frequency)) * amplitude;
+        total += amplitude;
+        amplitude *= gain;
+        frequency *= lacunarity;
+    }
+
+    sum / total
+}
+
+// Ken Perlin's improved noise, roughly within -1.0..=1.0 and 0.0 at every integer point
+pub fn perlin(p: &Tuple) -> FP {
+    let (x0, y0, z0) = (p.x.floor(), p.y.floor(), p.z.floor());
+    let (x, y, z) = (p.x - x0, p.y - y0, p.z - z0);
+    let (i, j, k) = (x0 as i64, y0 as i64, z0 as i64);
+    let (u, v, w) = (fade(x), fade(y), fade(z));
+
+    let corner = |di: i64, dj: i64, dk: i64| {
+        grad(
+            hash(i + di, j + dj, k + dk),
+            x - di as FP,
+            y - dj as FP,
+            z - dk as FP,
+        )
+    };
+
+    lerp(
+        w,
+        lerp(
+            v,
+            lerp(u, corner(0, 0, 0), corner(1, 0, 0)),
+            lerp(u, corner(0, 1, 0), corner(1, 1, 0)),
+        ),
+        lerp(
+            v,
+            lerp(u, corner(0, 0, 1), corner(1, 0, 1)),
+            lerp(u, corner(0, 1, 1), corner(1, 1, 1)),
+        ),
+    )
+}
+
+fn fade(t: FP) -> FP {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(t: FP, a: FP, b: FP) -> FP {
+    a + t * (b - a)
+}
+
+// Scrambles the lattice coordinates in place of Perlin's permutation table, so the noise
+// doesn't repeat every 256 units
+fn hash(i: i64, j: i64, k: i64) -> u64 {
+    let mut h = (i as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15)
+        ^ (j as u64).wrapping_mul(0xc2b2_ae3d_27d4_eb4f)
+        ^ (k as u64).wrapping_mul(0x1656_67b1_9e37_79f9);
+    h ^= h >> 33;
+    h = h.wrapping_mul(0xff51_afd7_ed55_8ccd);
+    h ^= h >> 33;
+    h
+}
+
+// One of the twelve directions towards the edges of a cube, dotted with (x, y, z)
+fn grad(hash: u64, x: FP, y: FP, z: FP) -> FP {
+    match hash % 12 {
+        0 => x + y,
+        1 => -x + y,
+        2 => x - y,
+        3 => -x - y,
+        4 => x + z,
+        5 => -x + z,
+        6 => x - z,
+        7 => -x - z,
+        8 => y + z,
+        9 => -y + z,
+        10 => y - z,
+        _ => -y - z,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pattern::noise::*;
+
+    #[test]
+    fn noise_vanishes_at_lattice_points() {
+        for p in [
+            point(0.0, 0.0, 0.0),
+            point(3.0, -2.0, 7.0),
+            point(-5.0, 1.0, -1.0),
+        ] {
+            assert_eq!(0.0, perlin(&p));
+        }
+    }
+
+    #[test]
+    fn noise_is_deterministic_and_bounded() {
+        for i in 0..1000 {
+            let p = point(i as FP * 0.173, i as FP * -0.291, i as FP * 0.057);
+            let n = perlin(&p);
+
+            assert_eq!(n, perlin(&p));
+            assert!((-1.1..=1.1).contains(&n));
+        }
+    }
+
+    #[test]
+    fn noise_is_continuous() {
+        let p = point(1.3, 2.7, -0.4);
+        let q = p + vector(0.001, 0.001, 0.001);
+
+        assert!((perlin(&p) - perlin(&q)).abs() < 0.01);
+        assert_ne!(perlin(&p), perlin(&point(1.3, 2.7, 5.6)));
+    }
+
+    #[test]
+    fn single_octave_is_plain_noise() {
+        let p = point(0.4, 1.9, -3.2);
+
+        assert_eq!(perlin(&p), fbm(&p, 1, 2.0, 0.5));
+        assert_eq!(perlin(&p).abs(), turbulence(&p, 1, 2.0, 0.5));
+    }
+
+    #[test]
+    fn octaves_add_finer_detail() {
+        let p = point(0.4, 1.9, -3.2);
+        let expected = (perlin(&p) + perlin(&(p * 2.0)) * 0.5) / 1.5;
+
+        assert!((expected - fbm(&p, 2, 2.0, 0.5)).abs() < crate::EPSILON);
+    }
+
+    #[test]
+    fn noise_pattern_stays_between_its_colors() {
+        let mut n = Noise::new(Color::black(), Color::white(), 4, 2.0, 0.5);
+        assert_eq!(Color::new(0.5, 0.5, 0.5), n.color_at(&point(0.0, 0.0, 0.0)));
+
+        n.turbulence = true;
+        assert_eq!(Color::black(), n.color_at(&point(0.0, 0.0, 0.0)));
+        for i in 0..100 {
+            let c = n.color_at(&point(i as FP * 0.31, 0.5, i as FP * 0.17));
+            assert!((0.0..=1.0).contains(&c.red));
+        }
+    }
+}
@@ -0,0 +1,75 @@
+use crate::color::Color;
+use crate::pattern::noise::fbm;
+use crate::pattern::*;
+use crate::tuple::*;
+use crate::FP;
+
+const OCTAVES: usize = 3;
+
+// Offsets so that each axis is jittered by a different stretch of noise
+const Y_OFFSET: FP = 31.7;
+const Z_OFFSET: FP = 57.3;
+
+// Moves each point by up to `scale` in a smoothly varying random direction before looking it
+// up in the wrapped pattern, so that straight stripes and perfect rings wobble
+#[derive(Debug, PartialEq, Clone)]
+pub struct Perturbed {
+    pub pattern: Box<Pattern>,
+    pub scale: FP,
+}
+
+impl Perturbed {
+    pub fn new(pattern: Pattern, scale: FP) -> Self {
+        Self {
+            pattern: Box::new(pattern),
+            scale,
+        }
+    }
+
+    pub fn color_at(&self, p: &Tuple) -> Color {
+        let offset = vector(
+            fbm(p, OCTAVES, 2.0, 0.5),
+            fbm(&(*p + vector(Y_OFFSET, 0.0, 0.0)), OCTAVES, 2.0, 0.5),
+            fbm(&(*p + vector(0.0, 0.0, Z_OFFSET)), OCTAVES, 2.0, 0.5),
+        );
+
+        self.pattern.color_at(&(*p + offset * self.scale))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pattern::perturbed::*;
+
+    #[test]
+    fn unscaled_perturbation_leaves_pattern_unchanged() {
+        let stripe = Pattern::stripe(Color::white(), Color::black());
+        let p = Perturbed::new(stripe.clone(), 0.0);
+
+        for i in 0..20 {
+            let point = point(i as FP * 0.37, 0.2, 0.9);
+            assert_eq!(stripe.color_at(&point), p.color_at(&point));
+        }
+    }
+
+    #[test]
+    fn perturbation_moves_stripe_edges() {
+        let stripe = Pattern::stripe(Color::white(), Color::black());
+        let p = Perturbed::new(stripe.clone(), 0.5);
+
+        let moved = (0..100)
+            .map(|i| point(i as FP * 0.13 + 0.05, i as FP * 0.71, 0.3))
+            .filter(|point| stripe.color_at(point) != p.color_at(point))
+            .count();
+        assert!(moved > 0);
+    }
+
+    #[test]
+    fn perturbed_pattern_uses_child_transform() {
+        let mut stripe = Pattern::stripe(Color::white(), Color::black());
+        stripe.set_transform(scaling(100.0, 1.0, 1.0));
+        let p = Perturbed::new(stripe, 0.5);
+
+        assert_eq!(Color::white(), p.color_at(&point(50.0, 0.3, 0.7)));
+    }
+}
@@ -61,9 +61,9 @@ mod tests {
     }
 
     #[test]
-    fn stripe_pattern__with_object_transform() {
+    fn stripe_pattern_with_object_transform() {
         let mut s = Shape::sphere();
-        s.transform = scaling(2.0, 2.0, 2.0);
+        s.set_transform(scaling(2.0, 2.0, 2.0));
         let p = Pattern::stripe(Color::white(), Color::black());
 
         assert_eq!(Color::white(), p.color_at_object(&s, &point(1.5, 0.0, 0.0)));
@@ -73,7 +73,7 @@ mod tests {
     fn stripe_pattern_transform() {
         let s = Shape::sphere();
         let mut p = Pattern::stripe(Color::white(), Color::black());
-        p.transform = scaling(2.0, 2.0, 2.0);
+        p.set_transform(scaling(2.0, 2.0, 2.0));
 
         assert_eq!(Color::white(), p.color_at_object(&s, &point(1.5, 0.0, 0.0)));
     }
@@ -82,8 +82,8 @@ mod tests {
     fn stripe_pattern_and_obj_transform() {
         let mut s = Shape::sphere();
         let mut p = Pattern::stripe(Color::white(), Color::black());
-        s.transform = scaling(2.0, 2.0, 2.0);
-        p.transform = translation(0.5, 0.0, 0.0);
+        s.set_transform(scaling(2.0, 2.0, 2.0));
+        p.set_transform(translation(0.5, 0.0, 0.0));
 
         assert_eq!(Color::white(), p.color_at_object(&s, &point(2.5, 0.0, 0.0)));
     }
@@ -1,5 +1,4 @@
 use crate::matrix::*;
-use crate::shape::Shape;
 use crate::tuple::*;
 use crate::FP;
 
@@ -157,7 +156,7 @@ mod tests {
         let d = vector(0.0, 0.0, 1.0);
         let r = Ray::new(o, d);
         let mut s = Shape::sphere();
-        s.transform = scaling(2.0, 2.0, 2.0);
+        s.set_transform(scaling(2.0, 2.0, 2.0));
 
         let xs = s.intersect(&r);
 
@@ -172,7 +171,7 @@ mod tests {
         let d = vector(0.0, 0.0, 1.0);
         let r = Ray::new(o, d);
         let mut s = Shape::sphere();
-        s.transform = translation(5.0, 0.0, 0.0);
+        s.set_transform(translation(5.0, 0.0, 0.0));
 
         let xs = s.intersect(&r);
 
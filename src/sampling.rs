@@ -0,0 +1,129 @@
+use crate::tuple::*;
+use crate::FP;
+use crate::PI;
+
+// Two unit vectors perpendicular to `n` and to each other
+pub fn orthonormal_basis(n: &Tuple) -> (Tuple, Tuple) {
+    let a = if n.x.abs() > 0.9 {
+        vector(0.0, 1.0, 0.0)
+    } else {
+        vector(1.0, 0.0, 0.0)
+    };
+
+    let t = n.cross(&a).normalize();
+    let b = n.cross(&t);
+    (t, b)
+}
+
+// A random direction in the hemisphere around `n`, more likely the closer it is to `n` so
+// that the cosine term of diffuse reflection cancels with the sampling pdf
+pub fn cosine_weighted_direction(n: &Tuple) -> Tuple {
+    let (t, b) = orthonormal_basis(n);
+    let phi = 2.0 * PI * rand::random::<FP>();
+    let r2 = rand::random::<FP>();
+    let r = r2.sqrt();
+
+    t * (r * phi.cos()) + b * (r * phi.sin()) + *n * (1.0 - r2).sqrt()
+}
+
+pub fn uniform_sphere_direction() -> Tuple {
+    let z = 1.0 - 2.0 * rand::random::<FP>();
+    let r = (1.0 - z * z).max(0.0).sqrt();
+    let phi = 2.0 * PI * rand::random::<FP>();
+
+    vector(r * phi.cos(), r * phi.sin(), z)
+}
+
+// A direction chosen uniformly from the cone around `axis` whose half-angle has cosine
+// `cos_max`, returned along with the pdf of having chosen it
+pub fn uniform_cone_direction(axis: &Tuple, cos_max: FP) -> (Tuple, FP) {
+    let (t, b) = orthonormal_basis(axis);
+    let cos_theta = 1.0 - rand::random::<FP>() * (1.0 - cos_max);
+    let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+    let phi = 2.0 * PI * rand::random::<FP>();
+
+    let d = t * (sin_theta * phi.cos()) + b * (sin_theta * phi.sin()) + *axis * cos_theta;
+    (d, 1.0 / (2.0 * PI * (1.0 - cos_max)))
+}
+
+// A microfacet normal drawn from the GGX distribution around `n`, with pdf D(h) * (n . h)
+pub fn ggx_half_vector(n: &Tuple, alpha: FP) -> Tuple {
+    let (t, b) = orthonormal_basis(n);
+    let xi = rand::random::<FP>();
+    let cos_theta = ((1.0 - xi) / (1.0 + (alpha * alpha - 1.0) * xi)).sqrt();
+    let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+    let phi = 2.0 * PI * rand::random::<FP>();
+
+    t * (sin_theta * phi.cos()) + b * (sin_theta * phi.sin()) + *n * cos_theta
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sampling::*;
+    use crate::EPSILON;
+
+    #[test]
+    fn basis_is_orthonormal() {
+        for n in [
+            vector(0.0, 1.0, 0.0),
+            vector(1.0, 0.0, 0.0),
+            vector(1.0, 2.0, -3.0).normalize(),
+        ] {
+            let (t, b) = orthonormal_basis(&n);
+
+            assert!((1.0 - t.magnitude()).abs() < EPSILON);
+            assert!((1.0 - b.magnitude()).abs() < EPSILON);
+            assert!(t.dot(&n).abs() < EPSILON);
+            assert!(b.dot(&n).abs() < EPSILON);
+            assert!(t.dot(&b).abs() < EPSILON);
+        }
+    }
+
+    #[test]
+    fn cosine_weighted_directions_lie_in_hemisphere() {
+        let n = vector(0.0, 0.0, -1.0);
+
+        for _ in 0..100 {
+            let d = cosine_weighted_direction(&n);
+            assert!((1.0 - d.magnitude()).abs() < EPSILON);
+            assert!(d.dot(&n) >= 0.0);
+        }
+    }
+
+    #[test]
+    fn uniform_sphere_directions_are_unit_vectors() {
+        for _ in 0..100 {
+            assert!((1.0 - uniform_sphere_direction().magnitude()).abs() < EPSILON);
+        }
+    }
+
+    #[test]
+    fn cone_directions_stay_within_cone() {
+        let axis = vector(1.0, 1.0, 0.0).normalize();
+        let cos_max = (PI / 8.0).cos();
+
+        for _ in 0..100 {
+            let (d, pdf) = uniform_cone_direction(&axis, cos_max);
+            assert!((1.0 - d.magnitude()).abs() < EPSILON);
+            assert!(d.dot(&axis) >= cos_max - EPSILON);
+            assert!((1.0 / (2.0 * PI * (1.0 - cos_max)) - pdf).abs() < EPSILON);
+        }
+    }
+
+    #[test]
+    fn ggx_half_vectors_narrow_with_smoothness() {
+        let n = vector(0.0, 0.0, 1.0);
+        let mean_cos = |alpha: FP| {
+            (0..1000)
+                .map(|_| ggx_half_vector(&n, alpha).dot(&n))
+                .sum::<FP>()
+                / 1000.0
+        };
+
+        for _ in 0..100 {
+            assert!((1.0 - ggx_half_vector(&n, 0.5).magnitude()).abs() < EPSILON);
+        }
+        assert!(mean_cos(0.01) > 0.99);
+        assert!(mean_cos(0.01) > mean_cos(0.8));
+    }
+}
@@ -0,0 +1,207 @@
+use crate::bounds::BoundingBox;
+use crate::intersection::*;
+use crate::ray::Ray;
+use crate::shape::cylinder::check_cap;
+use crate::shape::Shape;
+use crate::tuple::*;
+use crate::EPSILON;
+use crate::FP;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Cone {
+    pub minimum: FP,
+    pub maximum: FP,
+    pub closed: bool,
+}
+
+impl Cone {
+    pub fn new() -> Self {
+        Self {
+            minimum: -FP::INFINITY,
+            maximum: FP::INFINITY,
+            closed: false,
+        }
+    }
+
+    pub fn truncated(minimum: FP, maximum: FP, closed: bool) -> Self {
+        Self {
+            minimum,
+            maximum,
+            closed,
+        }
+    }
+}
+
+impl Cone {
+    pub fn local_intersect<'a>(&self, shape: &'a Shape, ray: &Ray) -> Intersections<'a> {
+        let mut xs = vec![];
+
+        let a = ray.direction.x * ray.direction.x - ray.direction.y * ray.direction.y
+            + ray.direction.z * ray.direction.z;
+        let b = 2.0 * ray.origin.x * ray.direction.x - 2.0 * ray.origin.y * ray.direction.y
+            + 2.0 * ray.origin.z * ray.direction.z;
+        let c =
+            ray.origin.x * ray.origin.x - ray.origin.y * ray.origin.y + ray.origin.z * ray.origin.z;
+
+        if a.abs() < EPSILON {
+            // The ray is parallel to one of the nappes so it can only cross the other one once
+            if b.abs() >= EPSILON {
+                let t = -c / (2.0 * b);
+                self.push_if_within(shape, ray, t, &mut xs);
+            }
+        } else {
+            let discriminant = b * b - 4.0 * a * c;
+
+            if discriminant >= 0.0 {
+                let mut t0 = (-b - discriminant.sqrt()) / (2.0 * a);
+                let mut t1 = (-b + discriminant.sqrt()) / (2.0 * a);
+                if t0 > t1 {
+                    std::mem::swap(&mut t0, &mut t1);
+                }
+
+                self.push_if_within(shape, ray, t0, &mut xs);
+                self.push_if_within(shape, ray, t1, &mut xs);
+            }
+        }
+
+        self.intersect_caps(shape, ray, &mut xs);
+        Intersections::new(xs)
+    }
+
+    fn push_if_within<'a>(&self, shape: &'a Shape, ray: &Ray, t: FP, xs: &mut Vec<Intersection<'a>>) {
+        let y = ray.origin.y + t * ray.direction.y;
+        if self.minimum < y && y < self.maximum {
+            xs.push(Intersection::new(t, shape));
+        }
+    }
+
+    fn intersect_caps<'a>(&self, shape: &'a Shape, ray: &Ray, xs: &mut Vec<Intersection<'a>>) {
+        if !self.closed || ray.direction.y.abs() < EPSILON {
+            return;
+        }
+
+        // The radius of a cone at any cap is the absolute y value of that cap
+        for limit in [self.minimum, self.maximum] {
+            let t = (limit - ray.origin.y) / ray.direction.y;
+            if check_cap(ray, t, limit.abs()) {
+                xs.push(Intersection::new(t, shape));
+            }
+        }
+    }
+
+    pub fn local_normal_at(&self, object_point: Tuple) -> Tuple {
+        let dist = object_point.x * object_point.x + object_point.z * object_point.z;
+
+        if dist < self.maximum * self.maximum && object_point.y >= self.maximum - EPSILON {
+            vector(0.0, 1.0, 0.0)
+        } else if dist < self.minimum * self.minimum && object_point.y <= self.minimum + EPSILON {
+            vector(0.0, -1.0, 0.0)
+        } else {
+            let mut y = dist.sqrt();
+            if object_point.y > 0.0 {
+                y = -y;
+            }
+            vector(object_point.x, y, object_point.z)
+        }
+    }
+
+    pub fn bounds(&self) -> BoundingBox {
+        let limit = self.minimum.abs().max(self.maximum.abs());
+        BoundingBox::new(
+            point(-limit, self.minimum, -limit),
+            point(limit, self.maximum, limit),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::shape::cone::*;
+
+    #[test]
+    fn intersecting_cone_with_ray() {
+        let c = Shape::cone();
+        let cases = [
+            (point(0.0, 0.0, -5.0), vector(0.0, 0.0, 1.0), 5.0, 5.0),
+            (
+                point(0.0, 0.0, -5.0),
+                vector(1.0, 1.0, 1.0),
+                8.66025,
+                8.66025,
+            ),
+            (
+                point(1.0, 1.0, -5.0),
+                vector(-0.5, -1.0, 1.0),
+                4.55006,
+                49.44994,
+            ),
+        ];
+
+        for (origin, direction, t0, t1) in cases {
+            let xs = c.intersect(&Ray::new(origin, direction.normalize()));
+            assert_eq!(2, xs.len());
+            assert!((t0 - xs[0].t).abs() < EPSILON);
+            assert!((t1 - xs[1].t).abs() < EPSILON);
+        }
+    }
+
+    #[test]
+    fn intersecting_cone_parallel_to_one_half() {
+        let c = Shape::cone();
+        let r = Ray::new(point(0.0, 0.0, -1.0), vector(0.0, 1.0, 1.0).normalize());
+        let xs = c.intersect(&r);
+
+        assert_eq!(1, xs.len());
+        assert!((0.35355 - xs[0].t).abs() < EPSILON);
+    }
+
+    #[test]
+    fn intersecting_cone_end_caps() {
+        let c = Shape::truncated_cone(-0.5, 0.5, true);
+        let cases = [
+            (point(0.0, 0.0, -5.0), vector(0.0, 1.0, 0.0), 0),
+            (point(0.0, 0.0, -0.25), vector(0.0, 1.0, 1.0), 2),
+            (point(0.0, 0.0, -0.25), vector(0.0, 1.0, 0.0), 4),
+        ];
+
+        for (origin, direction, count) in cases {
+            let xs = c.intersect(&Ray::new(origin, direction.normalize()));
+            assert_eq!(count, xs.len());
+        }
+    }
+
+    #[test]
+    fn normal_on_cone() {
+        let c = Cone::new();
+        let cases = [
+            (point(0.0, 0.0, 0.0), vector(0.0, 0.0, 0.0)),
+            (point(1.0, 1.0, 1.0), vector(1.0, -(2.0 as FP).sqrt(), 1.0)),
+            (point(-1.0, -1.0, 0.0), vector(-1.0, 1.0, 0.0)),
+        ];
+
+        for (p, n) in cases {
+            assert_eq!(n, c.local_normal_at(p));
+        }
+    }
+
+    #[test]
+    fn bounded_cone_has_bounding_box() {
+        let b = Cone::truncated(-5.0, 3.0, false).bounds();
+        assert_eq!(point(-5.0, -5.0, -5.0), b.min);
+        assert_eq!(point(5.0, 3.0, 5.0), b.max);
+        assert!(!Cone::new().bounds().is_finite());
+    }
+
+    #[test]
+    fn normal_on_cone_end_caps() {
+        let c = Cone::truncated(-1.0, 1.0, true);
+        assert_eq!(
+            vector(0.0, -1.0, 0.0),
+            c.local_normal_at(point(0.5, -1.0, 0.0))
+        );
+        assert_eq!(
+            vector(0.0, 1.0, 0.0),
+            c.local_normal_at(point(0.0, 1.0, 0.5))
+        );
+    }
+}
@@ -0,0 +1,224 @@
+use crate::bounds::BoundingBox;
+use crate::intersection::*;
+use crate::ray::Ray;
+use crate::shape::Shape;
+use crate::tuple::*;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum CsgOperation {
+    Union,
+    Intersection,
+    Difference,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Csg {
+    pub operation: CsgOperation,
+    pub left: Box<Shape>,
+    pub right: Box<Shape>,
+}
+
+impl Csg {
+    pub fn new(operation: CsgOperation, left: Shape, right: Shape) -> Self {
+        Self {
+            operation,
+            left: Box::new(left),
+            right: Box::new(right),
+        }
+    }
+}
+
+impl Csg {
+    pub fn local_intersect(&self, ray: &Ray) -> Intersections<'_> {
+        let mut xs: Vec<Intersection> = self
+            .left
+            .intersect(ray)
+            .intersections
+            .into_iter()
+            .chain(self.right.intersect(ray).intersections)
+            .collect();
+        xs.sort();
+
+        self.filter_intersections(Intersections::new(xs))
+    }
+
+    pub fn local_normal_at(&self, _: Tuple) -> Tuple {
+        panic!("CSG shapes don't have a normal, only their children do");
+    }
+
+    pub fn bounds(&self) -> BoundingBox {
+        let mut b = self.left.bounds();
+        b.add_box(&self.right.bounds());
+        b
+    }
+
+    pub fn filter_intersections<'a>(&self, xs: Intersections<'a>) -> Intersections<'a> {
+        // Begin outside of both children
+        let mut inl = false;
+        let mut inr = false;
+
+        let mut result = vec![];
+
+        for i in xs.intersections {
+            let lhit = self.left.includes(i.obj);
+
+            if intersection_allowed(self.operation, lhit, inl, inr) {
+                result.push(i);
+            }
+
+            if lhit {
+                inl = !inl;
+            } else {
+                inr = !inr;
+            }
+        }
+
+        Intersections::new(result)
+    }
+}
+
+// Decides whether a hit on one child lies on the surface of the combined solid
+pub fn intersection_allowed(op: CsgOperation, lhit: bool, inl: bool, inr: bool) -> bool {
+    match op {
+        CsgOperation::Union => (lhit && !inr) || (!lhit && !inl),
+        CsgOperation::Intersection => (lhit && inr) || (!lhit && inl),
+        CsgOperation::Difference => (lhit && !inr) || (!lhit && inl),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::matrix::*;
+    use crate::shape::csg::*;
+
+    #[test]
+    fn csg_created_with_operation_and_two_shapes() {
+        let s1 = Shape::sphere();
+        let s2 = Shape::cube();
+        let c = Csg::new(CsgOperation::Union, s1.clone(), s2.clone());
+
+        assert_eq!(CsgOperation::Union, c.operation);
+        assert_eq!(s1.get_id(), c.left.get_id());
+        assert_eq!(s2.get_id(), c.right.get_id());
+    }
+
+    #[test]
+    fn evaluating_rule_for_csg_operation() {
+        use CsgOperation::*;
+
+        let cases = [
+            (Union, true, true, true, false),
+            (Union, true, true, false, true),
+            (Union, true, false, true, false),
+            (Union, true, false, false, true),
+            (Union, false, true, true, false),
+            (Union, false, true, false, false),
+            (Union, false, false, true, true),
+            (Union, false, false, false, true),
+            (Intersection, true, true, true, true),
+            (Intersection, true, true, false, false),
+            (Intersection, true, false, true, true),
+            (Intersection, true, false, false, false),
+            (Intersection, false, true, true, true),
+            (Intersection, false, true, false, true),
+            (Intersection, false, false, true, false),
+            (Intersection, false, false, false, false),
+            (Difference, true, true, true, false),
+            (Difference, true, true, false, true),
+            (Difference, true, false, true, false),
+            (Difference, true, false, false, true),
+            (Difference, false, true, true, true),
+            (Difference, false, true, false, true),
+            (Difference, false, false, true, false),
+            (Difference, false, false, false, false),
+        ];
+
+        for (op, lhit, inl, inr, result) in cases {
+            assert_eq!(result, intersection_allowed(op, lhit, inl, inr));
+        }
+    }
+
+    #[test]
+    fn filtering_list_of_intersections() {
+        let cases = [
+            (CsgOperation::Union, 0, 3),
+            (CsgOperation::Intersection, 1, 2),
+            (CsgOperation::Difference, 0, 1),
+        ];
+
+        for (op, x0, x1) in cases {
+            let s1 = Shape::sphere();
+            let s2 = Shape::cube();
+            let c = Csg::new(op, s1.clone(), s2.clone());
+            let xs = vec![
+                Intersection::new(1.0, &s1),
+                Intersection::new(2.0, &s2),
+                Intersection::new(3.0, &s1),
+                Intersection::new(4.0, &s2),
+            ];
+
+            let result = c.filter_intersections(Intersections::new(xs.clone()));
+
+            assert_eq!(2, result.len());
+            assert_eq!(xs[x0], result[0]);
+            assert_eq!(xs[x1], result[1]);
+        }
+    }
+
+    #[test]
+    fn csg_has_bounding_box_containing_its_children() {
+        let left = Shape::sphere();
+        let mut right = Shape::sphere();
+        right.set_transform(translation(2.0, 3.0, 4.0));
+
+        let b = Csg::new(CsgOperation::Difference, left, right).bounds();
+
+        assert_eq!(point(-1.0, -1.0, -1.0), b.min);
+        assert_eq!(point(3.0, 4.0, 5.0), b.max);
+    }
+
+    #[test]
+    fn ray_misses_csg_object() {
+        let c = Shape::csg(CsgOperation::Union, Shape::sphere(), Shape::cube());
+        let r = Ray::new(point(0.0, 2.0, -5.0), vector(0.0, 0.0, 1.0));
+        assert_eq!(0, c.intersect(&r).len());
+    }
+
+    #[test]
+    fn ray_hits_csg_object() {
+        let s1 = Shape::sphere();
+        let mut s2 = Shape::sphere();
+        s2.set_transform(translation(0.0, 0.0, 0.5));
+        let c = Shape::csg(CsgOperation::Union, s1.clone(), s2.clone());
+
+        let r = Ray::new(point(0.0, 0.0, -5.0), vector(0.0, 0.0, 1.0));
+        let xs = c.intersect(&r);
+
+        assert_eq!(2, xs.len());
+        assert_eq!(4.0, xs[0].t);
+        assert_eq!(s1.get_id(), xs[0].obj.get_id());
+        assert_eq!(6.5, xs[1].t);
+        assert_eq!(s2.get_id(), xs[1].obj.get_id());
+    }
+
+    #[test]
+    fn csg_children_inherit_transform() {
+        let mut s = Shape::sphere();
+        s.set_transform(scaling(0.5, 0.5, 0.5));
+        let mut c = Shape::csg(CsgOperation::Difference, Shape::cube(), s);
+        c.set_transform(translation(0.0, 0.0, 10.0));
+
+        let r = Ray::new(point(0.0, 0.0, 0.0), vector(0.0, 0.0, 1.0));
+        let xs = c.intersect(&r);
+
+        assert_eq!(4, xs.len());
+        assert_eq!(9.0, xs[0].t);
+        assert_eq!(9.5, xs[1].t);
+        assert_eq!(10.5, xs[2].t);
+        assert_eq!(11.0, xs[3].t);
+        assert_eq!(
+            vector(0.0, 0.0, -1.0),
+            xs[0].obj.normal_at(point(0.0, 0.0, 9.0))
+        );
+    }
+}
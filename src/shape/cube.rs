@@ -0,0 +1,142 @@
+use crate::bounds::BoundingBox;
+use crate::intersection::*;
+use crate::ray::Ray;
+use crate::shape::Shape;
+use crate::tuple::*;
+use crate::EPSILON;
+use crate::FP;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Cube {}
+
+impl Cube {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl Cube {
+    pub fn local_intersect<'a>(&self, shape: &'a Shape, ray: &Ray) -> Intersections<'a> {
+        let (xtmin, xtmax) = Self::check_axis(ray.origin.x, ray.direction.x);
+        let (ytmin, ytmax) = Self::check_axis(ray.origin.y, ray.direction.y);
+        let (ztmin, ztmax) = Self::check_axis(ray.origin.z, ray.direction.z);
+
+        let tmin = xtmin.max(ytmin).max(ztmin);
+        let tmax = xtmax.min(ytmax).min(ztmax);
+
+        if tmin > tmax {
+            return Intersections::new(vec![]);
+        }
+
+        Intersections::new(vec![
+            Intersection::new(tmin, shape),
+            Intersection::new(tmax, shape),
+        ])
+    }
+
+    pub fn local_normal_at(&self, object_point: Tuple) -> Tuple {
+        let x = object_point.x.abs();
+        let y = object_point.y.abs();
+        let z = object_point.z.abs();
+        let maxc = x.max(y).max(z);
+
+        if maxc == x {
+            vector(object_point.x, 0.0, 0.0)
+        } else if maxc == y {
+            vector(0.0, object_point.y, 0.0)
+        } else {
+            vector(0.0, 0.0, object_point.z)
+        }
+    }
+
+    pub fn bounds(&self) -> BoundingBox {
+        BoundingBox::new(point(-1.0, -1.0, -1.0), point(1.0, 1.0, 1.0))
+    }
+
+    fn check_axis(origin: FP, direction: FP) -> (FP, FP) {
+        let tmin_numerator = -1.0 - origin;
+        let tmax_numerator = 1.0 - origin;
+
+        let (tmin, tmax) = if direction.abs() >= EPSILON {
+            (tmin_numerator / direction, tmax_numerator / direction)
+        } else {
+            (tmin_numerator * FP::INFINITY, tmax_numerator * FP::INFINITY)
+        };
+
+        if tmin > tmax {
+            (tmax, tmin)
+        } else {
+            (tmin, tmax)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::shape::cube::*;
+
+    #[test]
+    fn ray_intersects_cube() {
+        let c = Shape::cube();
+        let cases = [
+            (point(5.0, 0.5, 0.0), vector(-1.0, 0.0, 0.0), 4.0, 6.0),
+            (point(-5.0, 0.5, 0.0), vector(1.0, 0.0, 0.0), 4.0, 6.0),
+            (point(0.5, 5.0, 0.0), vector(0.0, -1.0, 0.0), 4.0, 6.0),
+            (point(0.5, -5.0, 0.0), vector(0.0, 1.0, 0.0), 4.0, 6.0),
+            (point(0.5, 0.0, 5.0), vector(0.0, 0.0, -1.0), 4.0, 6.0),
+            (point(0.5, 0.0, -5.0), vector(0.0, 0.0, 1.0), 4.0, 6.0),
+            (point(0.0, 0.5, 0.0), vector(0.0, 0.0, 1.0), -1.0, 1.0),
+        ];
+
+        for (origin, direction, t1, t2) in cases {
+            let xs = c.intersect(&Ray::new(origin, direction));
+            assert_eq!(2, xs.len());
+            assert_eq!(t1, xs[0].t);
+            assert_eq!(t2, xs[1].t);
+        }
+    }
+
+    #[test]
+    fn ray_misses_cube() {
+        let c = Shape::cube();
+        let cases = [
+            (point(-2.0, 0.0, 0.0), vector(0.2673, 0.5345, 0.8018)),
+            (point(0.0, -2.0, 0.0), vector(0.8018, 0.2673, 0.5345)),
+            (point(0.0, 0.0, -2.0), vector(0.5345, 0.8018, 0.2673)),
+            (point(2.0, 0.0, 2.0), vector(0.0, 0.0, -1.0)),
+            (point(0.0, 2.0, 2.0), vector(0.0, -1.0, 0.0)),
+            (point(2.0, 2.0, 0.0), vector(-1.0, 0.0, 0.0)),
+        ];
+
+        for (origin, direction) in cases {
+            let xs = c.intersect(&Ray::new(origin, direction));
+            assert_eq!(0, xs.len());
+        }
+    }
+
+    #[test]
+    fn normal_on_surface_of_cube() {
+        let c = Cube::new();
+        let cases = [
+            (point(1.0, 0.5, -0.8), vector(1.0, 0.0, 0.0)),
+            (point(-1.0, -0.2, 0.9), vector(-1.0, 0.0, 0.0)),
+            (point(-0.4, 1.0, -0.1), vector(0.0, 1.0, 0.0)),
+            (point(0.3, -1.0, -0.7), vector(0.0, -1.0, 0.0)),
+            (point(-0.6, 0.3, 1.0), vector(0.0, 0.0, 1.0)),
+            (point(0.4, 0.4, -1.0), vector(0.0, 0.0, -1.0)),
+            (point(1.0, 1.0, 1.0), vector(1.0, 0.0, 0.0)),
+            (point(-1.0, -1.0, -1.0), vector(-1.0, 0.0, 0.0)),
+        ];
+
+        for (p, n) in cases {
+            assert_eq!(n, c.local_normal_at(p));
+        }
+    }
+
+    #[test]
+    fn cube_has_bounding_box() {
+        let b = Cube::new().bounds();
+        assert_eq!(point(-1.0, -1.0, -1.0), b.min);
+        assert_eq!(point(1.0, 1.0, 1.0), b.max);
+    }
+}
@@ -0,0 +1,230 @@
+use crate::bounds::BoundingBox;
+use crate::intersection::*;
+use crate::ray::Ray;
+use crate::shape::Shape;
+use crate::tuple::*;
+use crate::EPSILON;
+use crate::FP;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Cylinder {
+    pub minimum: FP,
+    pub maximum: FP,
+    pub closed: bool,
+}
+
+impl Cylinder {
+    pub fn new() -> Self {
+        Self {
+            minimum: -FP::INFINITY,
+            maximum: FP::INFINITY,
+            closed: false,
+        }
+    }
+
+    pub fn truncated(minimum: FP, maximum: FP, closed: bool) -> Self {
+        Self {
+            minimum,
+            maximum,
+            closed,
+        }
+    }
+}
+
+impl Cylinder {
+    pub fn local_intersect<'a>(&self, shape: &'a Shape, ray: &Ray) -> Intersections<'a> {
+        let mut xs = vec![];
+
+        let a = ray.direction.x * ray.direction.x + ray.direction.z * ray.direction.z;
+
+        // A ray parallel to the y axis can only hit the caps
+        if a.abs() >= EPSILON {
+            let b = 2.0 * ray.origin.x * ray.direction.x + 2.0 * ray.origin.z * ray.direction.z;
+            let c = ray.origin.x * ray.origin.x + ray.origin.z * ray.origin.z - 1.0;
+
+            let discriminant = b * b - 4.0 * a * c;
+
+            if discriminant < 0.0 {
+                return Intersections::new(vec![]);
+            }
+
+            let mut t0 = (-b - discriminant.sqrt()) / (2.0 * a);
+            let mut t1 = (-b + discriminant.sqrt()) / (2.0 * a);
+            if t0 > t1 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+
+            for t in [t0, t1] {
+                let y = ray.origin.y + t * ray.direction.y;
+                if self.minimum < y && y < self.maximum {
+                    xs.push(Intersection::new(t, shape));
+                }
+            }
+        }
+
+        self.intersect_caps(shape, ray, &mut xs);
+        Intersections::new(xs)
+    }
+
+    fn intersect_caps<'a>(&self, shape: &'a Shape, ray: &Ray, xs: &mut Vec<Intersection<'a>>) {
+        if !self.closed || ray.direction.y.abs() < EPSILON {
+            return;
+        }
+
+        for limit in [self.minimum, self.maximum] {
+            let t = (limit - ray.origin.y) / ray.direction.y;
+            if check_cap(ray, t, 1.0) {
+                xs.push(Intersection::new(t, shape));
+            }
+        }
+    }
+
+    pub fn local_normal_at(&self, object_point: Tuple) -> Tuple {
+        let dist = object_point.x * object_point.x + object_point.z * object_point.z;
+
+        if dist < 1.0 && object_point.y >= self.maximum - EPSILON {
+            vector(0.0, 1.0, 0.0)
+        } else if dist < 1.0 && object_point.y <= self.minimum + EPSILON {
+            vector(0.0, -1.0, 0.0)
+        } else {
+            vector(object_point.x, 0.0, object_point.z)
+        }
+    }
+
+    pub fn bounds(&self) -> BoundingBox {
+        BoundingBox::new(point(-1.0, self.minimum, -1.0), point(1.0, self.maximum, 1.0))
+    }
+}
+
+// Checks whether the intersection at t lies within the given radius of the y axis
+pub fn check_cap(ray: &Ray, t: FP, radius: FP) -> bool {
+    let x = ray.origin.x + t * ray.direction.x;
+    let z = ray.origin.z + t * ray.direction.z;
+
+    x * x + z * z <= radius * radius
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::shape::cylinder::*;
+
+    #[test]
+    fn ray_misses_cylinder() {
+        let c = Shape::cylinder();
+        let cases = [
+            (point(1.0, 0.0, 0.0), vector(0.0, 1.0, 0.0)),
+            (point(0.0, 0.0, 0.0), vector(0.0, 1.0, 0.0)),
+            (point(0.0, 0.0, -5.0), vector(1.0, 1.0, 1.0)),
+        ];
+
+        for (origin, direction) in cases {
+            let xs = c.intersect(&Ray::new(origin, direction.normalize()));
+            assert_eq!(0, xs.len());
+        }
+    }
+
+    #[test]
+    fn ray_strikes_cylinder() {
+        let c = Shape::cylinder();
+        let cases = [
+            (point(1.0, 0.0, -5.0), vector(0.0, 0.0, 1.0), 5.0, 5.0),
+            (point(0.0, 0.0, -5.0), vector(0.0, 0.0, 1.0), 4.0, 6.0),
+            (
+                point(0.5, 0.0, -5.0),
+                vector(0.1, 1.0, 1.0),
+                6.80798,
+                7.08872,
+            ),
+        ];
+
+        for (origin, direction, t0, t1) in cases {
+            let xs = c.intersect(&Ray::new(origin, direction.normalize()));
+            assert_eq!(2, xs.len());
+            assert!((t0 - xs[0].t).abs() < EPSILON);
+            assert!((t1 - xs[1].t).abs() < EPSILON);
+        }
+    }
+
+    #[test]
+    fn normal_on_cylinder() {
+        let c = Cylinder::new();
+        let cases = [
+            (point(1.0, 0.0, 0.0), vector(1.0, 0.0, 0.0)),
+            (point(0.0, 5.0, -1.0), vector(0.0, 0.0, -1.0)),
+            (point(0.0, -2.0, 1.0), vector(0.0, 0.0, 1.0)),
+            (point(-1.0, 1.0, 0.0), vector(-1.0, 0.0, 0.0)),
+        ];
+
+        for (p, n) in cases {
+            assert_eq!(n, c.local_normal_at(p));
+        }
+    }
+
+    #[test]
+    fn default_cylinder_is_infinite_and_open() {
+        let c = Cylinder::new();
+        assert_eq!(-FP::INFINITY, c.minimum);
+        assert_eq!(FP::INFINITY, c.maximum);
+        assert!(!c.closed);
+    }
+
+    #[test]
+    fn intersecting_constrained_cylinder() {
+        let c = Shape::truncated_cylinder(1.0, 2.0, false);
+        let cases = [
+            (point(0.0, 1.5, 0.0), vector(0.1, 1.0, 0.0), 0),
+            (point(0.0, 3.0, -5.0), vector(0.0, 0.0, 1.0), 0),
+            (point(0.0, 0.0, -5.0), vector(0.0, 0.0, 1.0), 0),
+            (point(0.0, 2.0, -5.0), vector(0.0, 0.0, 1.0), 0),
+            (point(0.0, 1.0, -5.0), vector(0.0, 0.0, 1.0), 0),
+            (point(0.0, 1.5, -2.0), vector(0.0, 0.0, 1.0), 2),
+        ];
+
+        for (origin, direction, count) in cases {
+            let xs = c.intersect(&Ray::new(origin, direction.normalize()));
+            assert_eq!(count, xs.len());
+        }
+    }
+
+    #[test]
+    fn intersecting_caps_of_closed_cylinder() {
+        let c = Shape::truncated_cylinder(1.0, 2.0, true);
+        let cases = [
+            (point(0.0, 3.0, 0.0), vector(0.0, -1.0, 0.0), 2),
+            (point(0.0, 3.0, -2.0), vector(0.0, -1.0, 2.0), 2),
+            (point(0.0, 4.0, -2.0), vector(0.0, -1.0, 1.0), 2),
+            (point(0.0, 0.0, -2.0), vector(0.0, 1.0, 2.0), 2),
+            (point(0.0, -1.0, -2.0), vector(0.0, 1.0, 1.0), 2),
+        ];
+
+        for (origin, direction, count) in cases {
+            let xs = c.intersect(&Ray::new(origin, direction.normalize()));
+            assert_eq!(count, xs.len());
+        }
+    }
+
+    #[test]
+    fn bounded_cylinder_has_bounding_box() {
+        let b = Cylinder::truncated(-5.0, 3.0, false).bounds();
+        assert_eq!(point(-1.0, -5.0, -1.0), b.min);
+        assert_eq!(point(1.0, 3.0, 1.0), b.max);
+        assert!(!Cylinder::new().bounds().is_finite());
+    }
+
+    #[test]
+    fn normal_on_cylinder_end_caps() {
+        let c = Cylinder::truncated(1.0, 2.0, true);
+        let cases = [
+            (point(0.0, 1.0, 0.0), vector(0.0, -1.0, 0.0)),
+            (point(0.5, 1.0, 0.0), vector(0.0, -1.0, 0.0)),
+            (point(0.0, 1.0, 0.5), vector(0.0, -1.0, 0.0)),
+            (point(0.0, 2.0, 0.0), vector(0.0, 1.0, 0.0)),
+            (point(0.5, 2.0, 0.0), vector(0.0, 1.0, 0.0)),
+            (point(0.0, 2.0, 0.5), vector(0.0, 1.0, 0.0)),
+        ];
+
+        for (p, n) in cases {
+            assert_eq!(n, c.local_normal_at(p));
+        }
+    }
+}
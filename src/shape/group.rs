@@ -0,0 +1,171 @@
+use crate::bounds::BoundingBox;
+use crate::intersection::*;
+use crate::ray::Ray;
+use crate::shape::Shape;
+use crate::tuple::*;
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Group {
+    pub children: Vec<Shape>,
+}
+
+impl Group {
+    pub fn new(children: Vec<Shape>) -> Self {
+        Self { children }
+    }
+}
+
+impl Group {
+    pub fn local_intersect(&self, ray: &Ray) -> Intersections<'_> {
+        let mut xs: Vec<Intersection> = self
+            .children
+            .iter()
+            .flat_map(|c| c.intersect(ray).intersections)
+            .collect();
+        xs.sort();
+        Intersections::new(xs)
+    }
+
+    pub fn local_normal_at(&self, _: Tuple) -> Tuple {
+        panic!("Groups don't have a normal, only their children do");
+    }
+
+    pub fn bounds(&self) -> BoundingBox {
+        let mut b = BoundingBox::empty();
+        for c in self.children.iter() {
+            b.add_box(&c.bounds());
+        }
+        b
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::matrix::*;
+    use crate::shape::group::*;
+    use crate::FP;
+    use std::f64::consts::PI;
+
+    #[test]
+    fn creating_new_group() {
+        let g = Group::new(vec![]);
+        assert_eq!(0, g.children.len());
+    }
+
+    #[test]
+    fn adding_child_to_group() {
+        let mut g = Shape::group(vec![]);
+        let s = Shape::test();
+        g.add_child(s.clone());
+
+        if let crate::shape::Shapes::Group(group) = &g.shape {
+            assert_eq!(1, group.children.len());
+            assert_eq!(s.get_id(), group.children[0].get_id());
+        } else {
+            panic!("Expected a group");
+        }
+    }
+
+    #[test]
+    fn intersecting_ray_with_empty_group() {
+        let g = Shape::group(vec![]);
+        let r = Ray::new(point(0.0, 0.0, 0.0), vector(0.0, 0.0, 1.0));
+        assert_eq!(0, g.intersect(&r).len());
+    }
+
+    #[test]
+    fn intersecting_ray_with_nonempty_group() {
+        let s1 = Shape::sphere();
+        let mut s2 = Shape::sphere();
+        s2.set_transform(translation(0.0, 0.0, -3.0));
+        let mut s3 = Shape::sphere();
+        s3.set_transform(translation(5.0, 0.0, 0.0));
+
+        let g = Shape::group(vec![s1.clone(), s2.clone(), s3]);
+        let r = Ray::new(point(0.0, 0.0, -5.0), vector(0.0, 0.0, 1.0));
+        let xs = g.intersect(&r);
+
+        assert_eq!(4, xs.len());
+        assert_eq!(s2.get_id(), xs[0].obj.get_id());
+        assert_eq!(s2.get_id(), xs[1].obj.get_id());
+        assert_eq!(s1.get_id(), xs[2].obj.get_id());
+        assert_eq!(s1.get_id(), xs[3].obj.get_id());
+    }
+
+    #[test]
+    fn intersecting_transformed_group() {
+        let mut s = Shape::sphere();
+        s.set_transform(translation(5.0, 0.0, 0.0));
+        let mut g = Shape::group(vec![s]);
+        g.set_transform(scaling(2.0, 2.0, 2.0));
+
+        let r = Ray::new(point(10.0, 0.0, -10.0), vector(0.0, 0.0, 1.0));
+        assert_eq!(2, g.intersect(&r).len());
+    }
+
+    #[test]
+    fn group_has_bounding_box_containing_its_children() {
+        let mut s = Shape::sphere();
+        s.set_transform(translation(2.0, 5.0, -3.0) * scaling(2.0, 2.0, 2.0));
+        let mut c = Shape::truncated_cylinder(-2.0, 2.0, false);
+        c.set_transform(translation(-4.0, -1.0, 4.0) * scaling(0.5, 1.0, 0.5));
+
+        let b = Group::new(vec![s, c]).bounds();
+
+        assert_eq!(point(-4.5, -3.0, -5.0), b.min);
+        assert_eq!(point(4.0, 7.0, 4.5), b.max);
+    }
+
+    fn nested_groups() -> Shape {
+        let mut s = Shape::sphere();
+        s.set_transform(translation(5.0, 0.0, 0.0));
+        let mut g2 = Shape::group(vec![s]);
+        g2.set_transform(scaling(1.0, 2.0, 3.0));
+        let mut g1 = Shape::group(vec![g2]);
+        g1.set_transform(rotation_y(PI / 2.0));
+        g1
+    }
+
+    #[test]
+    fn converting_point_from_world_to_object_space() {
+        let mut s = Shape::sphere();
+        s.set_transform(translation(5.0, 0.0, 0.0));
+        let mut g2 = Shape::group(vec![s]);
+        g2.set_transform(scaling(2.0, 2.0, 2.0));
+        let mut g1 = Shape::group(vec![g2]);
+        g1.set_transform(rotation_y(PI / 2.0));
+
+        let r = Ray::new(point(0.0, 0.0, 0.0), vector(0.0, 0.0, -1.0));
+        let hit = g1.intersect(&r).hit().unwrap();
+
+        assert_eq!(
+            point(0.0, 0.0, -1.0),
+            hit.obj.world_to_object(point(-2.0, 0.0, -10.0))
+        );
+    }
+
+    #[test]
+    fn converting_normal_from_object_to_world_space() {
+        let g1 = nested_groups();
+        let r = Ray::new(point(0.0, 0.0, 0.0), vector(0.0, 0.0, -1.0));
+        let hit = g1.intersect(&r).hit().unwrap();
+
+        let v = (3.0 as FP).sqrt() / 3.0;
+        assert_eq!(
+            vector(0.28571, 0.42857, -0.85714),
+            hit.obj.normal_to_world(vector(v, v, v))
+        );
+    }
+
+    #[test]
+    fn finding_normal_on_child_object() {
+        let g1 = nested_groups();
+        let r = Ray::new(point(0.0, 0.0, 0.0), vector(0.0, 0.0, -1.0));
+        let hit = g1.intersect(&r).hit().unwrap();
+
+        assert_eq!(
+            vector(0.2857, 0.42854, -0.85716),
+            hit.obj.normal_at(point(1.7321, 1.1547, -5.5774))
+        );
+    }
+}
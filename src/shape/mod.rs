@@ -1,22 +1,44 @@
+mod cone;
+mod cube;
+mod csg;
+mod cylinder;
+mod group;
 mod plane;
+mod smoothtriangle;
 mod sphere;
+mod triangle;
 
+use crate::bounds::BoundingBox;
 use crate::intersection::*;
 use crate::material::*;
 use crate::matrix::*;
 use crate::ray::*;
+use crate::shape::cone::Cone;
+use crate::shape::cube::Cube;
+use crate::shape::csg::Csg;
+use crate::shape::cylinder::Cylinder;
+use crate::shape::group::Group;
 use crate::shape::plane::Plane;
+use crate::shape::smoothtriangle::SmoothTriangle;
 use crate::shape::sphere::Sphere;
+use crate::shape::triangle::Triangle;
 use crate::tuple::*;
 use crate::EPSILON;
+use crate::FP;
 
 use uuid::Uuid;
 
+pub use crate::shape::csg::CsgOperation;
+
 #[derive(Debug, PartialEq, Clone)]
 pub struct Shape {
     id: Uuid,
     pub shape: Shapes,
-    pub transform: Matrix4,
+    transform: Matrix4,
+    inverse: Matrix4,
+    parent_inverse: Matrix4,
+    world_inverse: Matrix4,
+    world_inverse_transpose: Matrix4,
     pub material: Material,
 }
 
@@ -24,8 +46,12 @@ impl Shape {
     pub fn new(shape: Shapes) -> Self {
         Self {
             id: Uuid::new_v4(),
-            shape: shape,
+            shape,
             transform: Matrix4::identity(),
+            inverse: Matrix4::identity(),
+            parent_inverse: Matrix4::identity(),
+            world_inverse: Matrix4::identity(),
+            world_inverse_transpose: Matrix4::identity(),
             material: Material::new(),
         }
     }
@@ -34,10 +60,68 @@ impl Shape {
         Self::new(Shapes::Sphere(Sphere::new()))
     }
 
+    pub fn glass_sphere() -> Self {
+        let mut s = Self::sphere();
+        s.material.transparency = 1.0;
+        s.material.refractive_index = 1.5;
+        s
+    }
+
     pub fn plane() -> Self {
         Self::new(Shapes::Plane(Plane::new()))
     }
 
+    pub fn cube() -> Self {
+        Self::new(Shapes::Cube(Cube::new()))
+    }
+
+    pub fn cylinder() -> Self {
+        Self::new(Shapes::Cylinder(Cylinder::new()))
+    }
+
+    pub fn truncated_cylinder(minimum: FP, maximum: FP, closed: bool) -> Self {
+        Self::new(Shapes::Cylinder(Cylinder::truncated(minimum, maximum, closed)))
+    }
+
+    pub fn cone() -> Self {
+        Self::new(Shapes::Cone(Cone::new()))
+    }
+
+    pub fn truncated_cone(minimum: FP, maximum: FP, closed: bool) -> Self {
+        Self::new(Shapes::Cone(Cone::truncated(minimum, maximum, closed)))
+    }
+
+    pub fn triangle(p1: Tuple, p2: Tuple, p3: Tuple) -> Self {
+        Self::new(Shapes::Triangle(Triangle::new(p1, p2, p3)))
+    }
+
+    pub fn smooth_triangle(
+        p1: Tuple,
+        p2: Tuple,
+        p3: Tuple,
+        n1: Tuple,
+        n2: Tuple,
+        n3: Tuple,
+    ) -> Self {
+        Self::new(Shapes::SmoothTriangle(SmoothTriangle::new(
+            p1, p2, p3, n1, n2, n3,
+        )))
+    }
+
+    pub fn group(children: Vec<Shape>) -> Self {
+        let mut g = Self::new(Shapes::Group(Group::new(vec![])));
+        for c in children {
+            g.add_child(c);
+        }
+        g
+    }
+
+    pub fn csg(operation: CsgOperation, left: Shape, right: Shape) -> Self {
+        let mut s = Self::new(Shapes::Csg(Csg::new(operation, left, right)));
+        s.update_world_transform();
+        s
+    }
+
     pub fn test() -> Self {
         Self::new(Shapes::Test(TestShape::new()))
     }
@@ -46,59 +130,178 @@ impl Shape {
         self.id
     }
 
-    pub fn intersect(&self, r: &Ray) -> Intersections {
-        let local_ray = r.transform(self.transform.inverse());
+    pub fn transform(&self) -> Matrix4 {
+        self.transform
+    }
+
+    pub fn inverse(&self) -> Matrix4 {
+        self.inverse
+    }
 
-        match self.shape {
-            Shapes::Sphere(s) => Sphere::local_intersect(self.clone(), &local_ray),
-            Shapes::Plane(s) => Plane::local_intersect(self.clone(), &local_ray),
-            Shapes::Test(s) => TestShape::local_intersect(self.clone(), &local_ray),
+    pub fn set_transform(&mut self, transform: Matrix4) {
+        self.transform = transform;
+        self.inverse = transform.inverse();
+        self.update_world_transform();
+    }
+
+    pub fn add_child(&mut self, mut child: Shape) {
+        let world_inverse = self.world_inverse;
+        match &mut self.shape {
+            Shapes::Group(g) => {
+                child.set_parent_inverse(world_inverse);
+                g.children.push(child);
+            }
+            _ => panic!("Only groups can have children"),
+        }
+    }
+
+    // Every shape keeps the inverse of its parents' transforms combined with its own, so that
+    // normals and patterns can go straight from world space to object space without walking
+    // back up through the groups it belongs to
+    fn update_world_transform(&mut self) {
+        self.world_inverse = self.inverse * self.parent_inverse;
+        self.world_inverse_transpose = self.world_inverse.transpose();
+
+        let world_inverse = self.world_inverse;
+        match &mut self.shape {
+            Shapes::Group(g) => {
+                for c in g.children.iter_mut() {
+                    c.set_parent_inverse(world_inverse);
+                }
+            }
+            Shapes::Csg(c) => {
+                c.left.set_parent_inverse(world_inverse);
+                c.right.set_parent_inverse(world_inverse);
+            }
+            _ => {}
+        }
+    }
+
+    fn set_parent_inverse(&mut self, parent_inverse: Matrix4) {
+        self.parent_inverse = parent_inverse;
+        self.update_world_transform();
+    }
+
+    pub fn includes(&self, other: &Shape) -> bool {
+        match &self.shape {
+            Shapes::Group(g) => g.children.iter().any(|c| c.includes(other)),
+            Shapes::Csg(c) => c.left.includes(other) || c.right.includes(other),
+            _ => self.id == other.id,
+        }
+    }
+
+    pub fn intersect(&self, r: &Ray) -> Intersections<'_> {
+        let local_ray = r.transform(self.inverse);
+
+        match &self.shape {
+            Shapes::Sphere(s) => s.local_intersect(self, &local_ray),
+            Shapes::Plane(s) => s.local_intersect(self, &local_ray),
+            Shapes::Cube(s) => s.local_intersect(self, &local_ray),
+            Shapes::Cylinder(s) => s.local_intersect(self, &local_ray),
+            Shapes::Cone(s) => s.local_intersect(self, &local_ray),
+            Shapes::Triangle(s) => s.local_intersect(self, &local_ray),
+            Shapes::SmoothTriangle(s) => s.local_intersect(self, &local_ray),
+            Shapes::Group(s) => s.local_intersect(&local_ray),
+            Shapes::Csg(s) => s.local_intersect(&local_ray),
+            Shapes::Test(s) => s.local_intersect(self, &local_ray),
         }
     }
 
     pub fn normal_at(&self, p: Tuple) -> Tuple {
-        let local_point = self.transform.inverse() * p;
+        self.world_normal_at(p, 0.0, 0.0)
+    }
 
-        let local_normal = match self.shape {
-            Shapes::Sphere(s) => s.local_normal_at(local_point),
-            Shapes::Plane(s) => s.local_normal_at(local_point),
-            Shapes::Test(s) => s.local_normal_at(local_point),
+    pub fn normal_at_hit(&self, p: Tuple, hit: &Intersection) -> Tuple {
+        self.world_normal_at(p, hit.u, hit.v)
+    }
+
+    // Bounds of the shape in its parent's space, i.e. after applying its transform
+    pub fn bounds(&self) -> BoundingBox {
+        let local_bounds = match &self.shape {
+            Shapes::Sphere(s) => s.bounds(),
+            Shapes::Plane(s) => s.bounds(),
+            Shapes::Cube(s) => s.bounds(),
+            Shapes::Cylinder(s) => s.bounds(),
+            Shapes::Cone(s) => s.bounds(),
+            Shapes::Triangle(s) => s.bounds(),
+            Shapes::SmoothTriangle(s) => s.bounds(),
+            Shapes::Group(s) => s.bounds(),
+            Shapes::Csg(s) => s.bounds(),
+            Shapes::Test(s) => s.bounds(),
         };
 
-        let mut world_normal = self.transform.inverse().transpose() * local_normal;
+        local_bounds.transform(self.transform)
+    }
+
+    pub fn world_to_object(&self, p: Tuple) -> Tuple {
+        self.world_inverse * p
+    }
+
+    pub fn normal_to_world(&self, normal: Tuple) -> Tuple {
+        let mut world_normal = self.world_inverse_transpose * normal;
         world_normal.w = 0.0;
 
         world_normal.normalize()
     }
+
+    fn world_normal_at(&self, p: Tuple, u: FP, v: FP) -> Tuple {
+        let local_point = self.world_to_object(p);
+
+        let local_normal = match &self.shape {
+            Shapes::Sphere(s) => s.local_normal_at(local_point),
+            Shapes::Plane(s) => s.local_normal_at(local_point),
+            Shapes::Cube(s) => s.local_normal_at(local_point),
+            Shapes::Cylinder(s) => s.local_normal_at(local_point),
+            Shapes::Cone(s) => s.local_normal_at(local_point),
+            Shapes::Triangle(s) => s.local_normal_at(local_point),
+            Shapes::SmoothTriangle(s) => s.local_normal_at(local_point, u, v),
+            Shapes::Group(s) => s.local_normal_at(local_point),
+            Shapes::Csg(s) => s.local_normal_at(local_point),
+            Shapes::Test(s) => s.local_normal_at(local_point),
+        };
+
+        self.normal_to_world(local_normal)
+    }
 }
 
-#[derive(Debug, PartialEq, Clone, Copy)]
+#[derive(Debug, PartialEq, Clone)]
 pub enum Shapes {
     Sphere(Sphere),
     Plane(Plane),
+    Cube(Cube),
+    Cylinder(Cylinder),
+    Cone(Cone),
+    Triangle(Triangle),
+    SmoothTriangle(SmoothTriangle),
+    Group(Group),
+    Csg(Csg),
     Test(TestShape),
 }
 
 #[derive(Debug, PartialEq, Clone, Copy)]
-struct TestShape {}
+pub struct TestShape {}
 
 impl TestShape {
     pub fn new() -> Self {
         Self {}
     }
 
-    pub fn local_intersect(shape: Shape, ray: &Ray) -> Intersections {
+    pub fn local_intersect<'a>(&self, shape: &'a Shape, ray: &Ray) -> Intersections<'a> {
         if (ray.direction.y).abs() < EPSILON {
             return Intersections::new(vec![]);
         }
 
         let t = -ray.origin.y / ray.direction.y;
-        return Intersections::new(vec![Intersection::new(t, shape)]);
+        Intersections::new(vec![Intersection::new(t, shape)])
     }
 
     pub fn local_normal_at(&self, _: Tuple) -> Tuple {
         vector(0.0, 1.0, 0.0)
     }
+
+    pub fn bounds(&self) -> BoundingBox {
+        BoundingBox::new(point(-1.0, -1.0, -1.0), point(1.0, 1.0, 1.0))
+    }
 }
 
 #[cfg(test)]
@@ -108,14 +311,15 @@ mod tests {
     #[test]
     fn default_transform() {
         let s = Shape::test();
-        assert_eq!(s.transform, Matrix4::identity());
+        assert_eq!(s.transform(), Matrix4::identity());
     }
 
     #[test]
     fn assign_transform() {
         let mut s = Shape::test();
-        s.transform = translation(2.0, 3.0, 4.0);
-        assert_eq!(s.transform, translation(2.0, 3.0, 4.0));
+        s.set_transform(translation(2.0, 3.0, 4.0));
+        assert_eq!(s.transform(), translation(2.0, 3.0, 4.0));
+        assert_eq!(s.inverse(), translation(-2.0, -3.0, -4.0));
     }
 
     #[test]
@@ -132,4 +336,14 @@ mod tests {
         s.material = m;
         assert_eq!(s.material.ambient, 1.0);
     }
+
+    #[test]
+    fn shape_bounds_are_in_parent_space() {
+        let mut s = Shape::test();
+        s.set_transform(translation(1.0, -3.0, 5.0) * scaling(0.5, 2.0, 4.0));
+        let b = s.bounds();
+
+        assert_eq!(point(0.5, -5.0, 1.0), b.min);
+        assert_eq!(point(1.5, -1.0, 9.0), b.max);
+    }
 }
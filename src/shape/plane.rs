@@ -1,8 +1,10 @@
+use crate::bounds::BoundingBox;
 use crate::intersection::*;
 use crate::ray::Ray;
 use crate::tuple::*;
 use crate::Shape;
 use crate::EPSILON;
+use crate::FP;
 
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub struct Plane {}
@@ -14,18 +16,25 @@ impl Plane {
 }
 
 impl Plane {
-    pub fn local_intersect(shape: Shape, ray: &Ray) -> Intersections {
+    pub fn local_intersect<'a>(&self, shape: &'a Shape, ray: &Ray) -> Intersections<'a> {
         if (ray.direction.y).abs() < EPSILON {
             return Intersections::new(vec![]);
         }
 
         let t = -ray.origin.y / ray.direction.y;
-        return Intersections::new(vec![Intersection::new(t, shape)]);
+        Intersections::new(vec![Intersection::new(t, shape)])
     }
 
     pub fn local_normal_at(&self, _: Tuple) -> Tuple {
         vector(0.0, 1.0, 0.0)
     }
+
+    pub fn bounds(&self) -> BoundingBox {
+        BoundingBox::new(
+            point(-FP::INFINITY, 0.0, -FP::INFINITY),
+            point(FP::INFINITY, 0.0, FP::INFINITY),
+        )
+    }
 }
 
 #[cfg(test)]
@@ -44,6 +53,17 @@ mod tests {
         assert_eq!(n3, vector(0.0, 1.0, 0.0));
     }
 
+    #[test]
+    fn plane_is_unbounded_in_x_and_z() {
+        let b = Plane::new().bounds();
+        assert_eq!(-FP::INFINITY, b.min.x);
+        assert_eq!(0.0, b.min.y);
+        assert_eq!(-FP::INFINITY, b.min.z);
+        assert_eq!(FP::INFINITY, b.max.x);
+        assert_eq!(0.0, b.max.y);
+        assert_eq!(FP::INFINITY, b.max.z);
+    }
+
     #[test]
     fn intersect_ray_parallel() {
         let p = Shape::plane();
@@ -0,0 +1,100 @@
+use crate::bounds::BoundingBox;
+use crate::intersection::*;
+use crate::ray::Ray;
+use crate::shape::triangle::barycentric_intersect;
+use crate::shape::Shape;
+use crate::tuple::*;
+use crate::FP;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct SmoothTriangle {
+    pub p1: Tuple,
+    pub p2: Tuple,
+    pub p3: Tuple,
+    pub n1: Tuple,
+    pub n2: Tuple,
+    pub n3: Tuple,
+    pub e1: Tuple,
+    pub e2: Tuple,
+}
+
+impl SmoothTriangle {
+    pub fn new(p1: Tuple, p2: Tuple, p3: Tuple, n1: Tuple, n2: Tuple, n3: Tuple) -> Self {
+        Self {
+            p1,
+            p2,
+            p3,
+            n1,
+            n2,
+            n3,
+            e1: p2 - p1,
+            e2: p3 - p1,
+        }
+    }
+}
+
+impl SmoothTriangle {
+    pub fn local_intersect<'a>(&self, shape: &'a Shape, ray: &Ray) -> Intersections<'a> {
+        match barycentric_intersect(self.p1, self.e1, self.e2, ray) {
+            Some((t, u, v)) => Intersections::new(vec![Intersection::new_with_uv(t, shape, u, v)]),
+            None => Intersections::new(vec![]),
+        }
+    }
+
+    pub fn local_normal_at(&self, _: Tuple, u: FP, v: FP) -> Tuple {
+        self.n2 * u + self.n3 * v + self.n1 * (1.0 - u - v)
+    }
+
+    pub fn bounds(&self) -> BoundingBox {
+        let mut b = BoundingBox::empty();
+        b.add_point(self.p1);
+        b.add_point(self.p2);
+        b.add_point(self.p3);
+        b
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::shape::smoothtriangle::*;
+
+    fn test_triangle() -> Shape {
+        Shape::smooth_triangle(
+            point(0.0, 1.0, 0.0),
+            point(-1.0, 0.0, 0.0),
+            point(1.0, 0.0, 0.0),
+            vector(0.0, 1.0, 0.0),
+            vector(-1.0, 0.0, 0.0),
+            vector(1.0, 0.0, 0.0),
+        )
+    }
+
+    #[test]
+    fn intersection_with_smooth_triangle_stores_uv() {
+        let t = test_triangle();
+        let r = Ray::new(point(-0.2, 0.3, -2.0), vector(0.0, 0.0, 1.0));
+        let xs = t.intersect(&r);
+
+        assert!((0.45 - xs[0].u).abs() < crate::EPSILON);
+        assert!((0.25 - xs[0].v).abs() < crate::EPSILON);
+    }
+
+    #[test]
+    fn smooth_triangle_interpolates_normal() {
+        let t = test_triangle();
+        let i = Intersection::new_with_uv(1.0, &t, 0.45, 0.25);
+        let n = t.normal_at_hit(point(0.0, 0.0, 0.0), &i);
+
+        assert_eq!(vector(-0.5547, 0.83205, 0.0), n);
+    }
+
+    #[test]
+    fn preparing_normal_on_smooth_triangle() {
+        let t = test_triangle();
+        let i = Intersection::new_with_uv(1.0, &t, 0.45, 0.25);
+        let r = Ray::new(point(-0.2, 0.3, -2.0), vector(0.0, 0.0, 1.0));
+        let comps = i.prepare_computations(&r, &Intersections::new(vec![i]));
+
+        assert_eq!(vector(-0.5547, 0.83205, 0.0), comps.normalv);
+    }
+}
@@ -1,3 +1,4 @@
+use crate::bounds::BoundingBox;
 use crate::intersection::*;
 use crate::ray::Ray;
 use crate::shape::Shape;
@@ -13,7 +14,7 @@ impl Sphere {
 }
 
 impl Sphere {
-    pub fn local_intersect(shape: Shape, ray: &Ray) -> Intersections {
+    pub fn local_intersect<'a>(&self, shape: &'a Shape, ray: &Ray) -> Intersections<'a> {
         let sphere_to_ray = ray.origin - point(0.0, 0.0, 0.0);
         let a = ray.direction.dot(&ray.direction);
         let b = 2.0 * ray.direction.dot(&sphere_to_ray);
@@ -28,14 +29,18 @@ impl Sphere {
         let t1 = (-b - discriminant.sqrt()) / (2.0 * a);
         let t2 = (-b + discriminant.sqrt()) / (2.0 * a);
         Intersections::new(vec![
-            Intersection::new(t1, shape.clone()),
-            Intersection::new(t2, shape.clone()),
+            Intersection::new(t1, shape),
+            Intersection::new(t2, shape),
         ])
     }
 
     pub fn local_normal_at(&self, object_point: Tuple) -> Tuple {
         object_point - point(0.0, 0.0, 0.0)
     }
+
+    pub fn bounds(&self) -> BoundingBox {
+        BoundingBox::new(point(-1.0, -1.0, -1.0), point(1.0, 1.0, 1.0))
+    }
 }
 
 #[cfg(test)]
@@ -49,7 +54,7 @@ mod tests {
     #[test]
     fn sphere_default_transformation() {
         let s = Shape::sphere();
-        assert_eq!(Matrix4::identity(), s.transform);
+        assert_eq!(Matrix4::identity(), s.transform());
     }
 
     #[test]
@@ -84,7 +89,7 @@ mod tests {
     #[test]
     fn normal_of_translated_sphere() {
         let mut s = Shape::sphere();
-        s.transform = translation(0.0, 1.0, 0.0);
+        s.set_transform(translation(0.0, 1.0, 0.0));
 
         let n = s.normal_at(point(0.0, 1.70711, -0.70711));
         assert_eq!(vector(0.0, 0.70711, -0.70711), n);
@@ -93,7 +98,7 @@ mod tests {
     #[test]
     fn normal_of_transformed_sphere() {
         let mut s = Shape::sphere();
-        s.transform = scaling(1.0, 0.5, 1.0) * rotation_z(PI / 5.0);
+        s.set_transform(scaling(1.0, 0.5, 1.0) * rotation_z(PI / 5.0));
 
         let n = s.normal_at(point(
             0.0,
@@ -103,12 +108,19 @@ mod tests {
         assert_eq!(vector(0.0, 0.97014, -0.24254), n);
     }
 
+    #[test]
+    fn sphere_has_bounding_box() {
+        let b = Sphere::new().bounds();
+        assert_eq!(point(-1.0, -1.0, -1.0), b.min);
+        assert_eq!(point(1.0, 1.0, 1.0), b.max);
+    }
+
     #[test]
     fn assign_material() {
         let mut s = Shape::sphere();
         let mut m = Material::new();
         m.ambient = 1.0;
-        s.material = m;
+        s.material = m.clone();
 
         assert_eq!(m, s.material);
     }
@@ -0,0 +1,158 @@
+use crate::bounds::BoundingBox;
+use crate::intersection::*;
+use crate::ray::Ray;
+use crate::shape::Shape;
+use crate::tuple::*;
+use crate::EPSILON;
+use crate::FP;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Triangle {
+    pub p1: Tuple,
+    pub p2: Tuple,
+    pub p3: Tuple,
+    pub e1: Tuple,
+    pub e2: Tuple,
+    pub normal: Tuple,
+}
+
+impl Triangle {
+    pub fn new(p1: Tuple, p2: Tuple, p3: Tuple) -> Self {
+        let e1 = p2 - p1;
+        let e2 = p3 - p1;
+
+        Self {
+            p1,
+            p2,
+            p3,
+            e1,
+            e2,
+            normal: e2.cross(&e1).normalize(),
+        }
+    }
+}
+
+impl Triangle {
+    pub fn local_intersect<'a>(&self, shape: &'a Shape, ray: &Ray) -> Intersections<'a> {
+        match barycentric_intersect(self.p1, self.e1, self.e2, ray) {
+            Some((t, u, v)) => Intersections::new(vec![Intersection::new_with_uv(t, shape, u, v)]),
+            None => Intersections::new(vec![]),
+        }
+    }
+
+    pub fn local_normal_at(&self, _: Tuple) -> Tuple {
+        self.normal
+    }
+
+    pub fn bounds(&self) -> BoundingBox {
+        let mut b = BoundingBox::empty();
+        b.add_point(self.p1);
+        b.add_point(self.p2);
+        b.add_point(self.p3);
+        b
+    }
+}
+
+// Möller–Trumbore intersection returning t along with the barycentric u and v of the hit
+pub fn barycentric_intersect(p1: Tuple, e1: Tuple, e2: Tuple, ray: &Ray) -> Option<(FP, FP, FP)> {
+    let dir_cross_e2 = ray.direction.cross(&e2);
+    let det = e1.dot(&dir_cross_e2);
+
+    if det.abs() < EPSILON {
+        return None;
+    }
+
+    let f = 1.0 / det;
+    let p1_to_origin = ray.origin - p1;
+    let u = f * p1_to_origin.dot(&dir_cross_e2);
+    if !(0.0..=1.0).contains(&u) {
+        return None;
+    }
+
+    let origin_cross_e1 = p1_to_origin.cross(&e1);
+    let v = f * ray.direction.dot(&origin_cross_e1);
+    if v < 0.0 || (u + v) > 1.0 {
+        return None;
+    }
+
+    Some((f * e2.dot(&origin_cross_e1), u, v))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::shape::triangle::*;
+
+    #[test]
+    fn constructing_triangle() {
+        let p1 = point(0.0, 1.0, 0.0);
+        let p2 = point(-1.0, 0.0, 0.0);
+        let p3 = point(1.0, 0.0, 0.0);
+        let t = Triangle::new(p1, p2, p3);
+
+        assert_eq!(p1, t.p1);
+        assert_eq!(p2, t.p2);
+        assert_eq!(p3, t.p3);
+        assert_eq!(vector(-1.0, -1.0, 0.0), t.e1);
+        assert_eq!(vector(1.0, -1.0, 0.0), t.e2);
+        assert_eq!(vector(0.0, 0.0, -1.0), t.normal);
+    }
+
+    #[test]
+    fn normal_of_triangle() {
+        let t = Triangle::new(
+            point(0.0, 1.0, 0.0),
+            point(-1.0, 0.0, 0.0),
+            point(1.0, 0.0, 0.0),
+        );
+
+        assert_eq!(t.normal, t.local_normal_at(point(0.0, 0.5, 0.0)));
+        assert_eq!(t.normal, t.local_normal_at(point(-0.5, 0.75, 0.0)));
+        assert_eq!(t.normal, t.local_normal_at(point(0.5, 0.25, 0.0)));
+    }
+
+    #[test]
+    fn triangle_has_bounding_box() {
+        let t = Triangle::new(
+            point(-3.0, 7.0, 2.0),
+            point(6.0, 2.0, -4.0),
+            point(2.0, -1.0, -1.0),
+        );
+        let b = t.bounds();
+
+        assert_eq!(point(-3.0, -1.0, -4.0), b.min);
+        assert_eq!(point(6.0, 7.0, 2.0), b.max);
+    }
+
+    #[test]
+    fn ray_misses_triangle() {
+        let t = Shape::triangle(
+            point(0.0, 1.0, 0.0),
+            point(-1.0, 0.0, 0.0),
+            point(1.0, 0.0, 0.0),
+        );
+        let cases = [
+            (point(0.0, -1.0, -2.0), vector(0.0, 1.0, 0.0)),
+            (point(1.0, 1.0, -2.0), vector(0.0, 0.0, 1.0)),
+            (point(-1.0, 1.0, -2.0), vector(0.0, 0.0, 1.0)),
+            (point(0.0, -1.0, -2.0), vector(0.0, 0.0, 1.0)),
+        ];
+
+        for (origin, direction) in cases {
+            assert_eq!(0, t.intersect(&Ray::new(origin, direction)).len());
+        }
+    }
+
+    #[test]
+    fn ray_strikes_triangle() {
+        let t = Shape::triangle(
+            point(0.0, 1.0, 0.0),
+            point(-1.0, 0.0, 0.0),
+            point(1.0, 0.0, 0.0),
+        );
+        let r = Ray::new(point(0.0, 0.5, -2.0), vector(0.0, 0.0, 1.0));
+        let xs = t.intersect(&r);
+
+        assert_eq!(1, xs.len());
+        assert_eq!(2.0, xs[0].t);
+    }
+}
@@ -1,22 +1,53 @@
 use crate::Pattern;
+use crate::background::Background;
+use crate::bounds::BoundingBox;
+use crate::brdf::*;
+use crate::bvh::Bvh;
 use crate::intersection::*;
-use crate::light::PointLight;
+use crate::light::{luminance, Light, Lights};
+use crate::material::ShadingModel;
 use crate::matrix::*;
 use crate::ray::Ray;
-use crate::shape::Shape;
+use crate::sampling::*;
+use crate::shape::{Shape, Shapes};
 use crate::tuple::*;
 use crate::Color;
+use crate::FP;
+use crate::PI;
 
+use std::sync::OnceLock;
+use uuid::Uuid;
+
+// Paths that have bounced this many times may be ended early by Russian roulette
+const ROULETTE_DEPTH: usize = 3;
+
+// An emissive shape that the path tracer samples like a light, by aiming at the sphere
+// around its bounding box
+#[derive(Debug, Clone, Copy)]
+struct Emitter {
+    id: Uuid,
+    center: Tuple,
+    radius: FP,
+}
+
+// Objects are only reachable through add and objects_mut, so that the BVH built over them can't
+// go stale. It's built the first time the world is intersected after a change
 pub struct World {
-    pub objects: Vec<Shape>,
-    pub light: Option<PointLight>,
+    objects: Vec<Shape>,
+    pub lights: Vec<Light>,
+    pub background: Background,
+    bvh: OnceLock<Bvh>,
+    emitters: OnceLock<Vec<Emitter>>,
 }
 
 impl World {
     pub fn new() -> Self {
         Self {
             objects: vec![],
-            light: None,
+            lights: vec![],
+            background: Background::Color(Color::black()),
+            bvh: OnceLock::new(),
+            emitters: OnceLock::new(),
         }
     }
 
@@ -28,22 +59,67 @@ impl World {
         s1.material.diffuse = 0.7;
         s1.material.specular = 0.2;
 
-        s2.transform = scaling(0.5, 0.5, 0.5);
+        s2.set_transform(scaling(0.5, 0.5, 0.5));
 
         Self {
             objects: vec![s1, s2],
-            light: Some(PointLight::new(
+            lights: vec![Light::point(
                 point(-10.0, 10.0, -10.0),
                 Color::new(1.0, 1.0, 1.0),
-            )),
+            )],
+            background: Background::Color(Color::black()),
+            bvh: OnceLock::new(),
+            emitters: OnceLock::new(),
         }
     }
 
     pub fn add(&mut self, s: Shape) {
-        self.objects.push(s)
+        self.objects_mut().push(s);
+    }
+
+    pub fn objects(&self) -> &[Shape] {
+        &self.objects
+    }
+
+    // Throws away everything cached about the objects, since the caller may change any of them
+    pub fn objects_mut(&mut self) -> &mut Vec<Shape> {
+        self.bvh = OnceLock::new();
+        self.emitters = OnceLock::new();
+        &mut self.objects
+    }
+
+    // Builds the BVH ahead of time rather than on the first intersection
+    pub fn build_bvh(&mut self) {
+        self.bvh();
+    }
+
+    fn bvh(&self) -> &Bvh {
+        self.bvh.get_or_init(|| Bvh::build(&self.objects))
     }
 
-    pub fn intersect(&self, r: &Ray) -> Intersections {
+    // The emissive shapes the path tracer samples, found once for all the paths through the
+    // world rather than for each of them
+    fn emitters(&self) -> &[Emitter] {
+        self.emitters.get_or_init(|| self.find_emitters())
+    }
+
+    fn find_emitters(&self) -> Vec<Emitter> {
+        let mut emitters = vec![];
+        for o in self.objects.iter() {
+            collect_emitters(o, Matrix4::identity(), &mut emitters);
+        }
+        emitters
+    }
+
+    fn is_sampled_emitter(&self, obj: &Shape, emitters: &[Emitter]) -> bool {
+        emitters.iter().any(|e| e.id == obj.get_id())
+    }
+
+    pub fn intersect(&self, r: &Ray) -> Intersections<'_> {
+        self.bvh().intersect(&self.objects, r)
+    }
+
+    pub fn intersect_all(&self, r: &Ray) -> Intersections<'_> {
         let mut v: Vec<Intersection> = self
             .objects
             .iter()
@@ -53,35 +129,322 @@ impl World {
         Intersections::new(v)
     }
 
-    pub fn shade_hit(&self, comps: &Computations) -> Color {
-        comps.obj.material.lighting(
-            &comps.obj,
-            self.light.unwrap(),
-            comps.over_point,
-            comps.eyev,
-            comps.normalv,
-            self.is_shadowed(&comps.over_point),
-        )
+    pub fn shade_hit(&self, comps: &Computations, remaining: usize) -> Color {
+        self.shade(comps, remaining, true)
     }
 
-    pub fn color_at(&self, r: &Ray) -> Color {
+    // `remaining` is how many more bounces a ray may take, so two mirrors facing each other
+    // stop recursing once it runs out
+    pub fn color_at(&self, r: &Ray, remaining: usize) -> Color {
+        self.color_along(r, remaining, true)
+    }
+
+    pub fn reflected_color(&self, comps: &Computations, remaining: usize) -> Color {
+        self.reflected(comps, remaining, true)
+    }
+
+    pub fn refracted_color(&self, comps: &Computations, remaining: usize) -> Color {
+        self.refracted(comps, remaining, true)
+    }
+
+    // Blurred reflections and refractions average several rays, but only where `branch` is
+    // set. Each of those rays follows a single ray through any blurred surface it meets, so
+    // that glossy surfaces facing each other don't multiply their samples at every bounce
+    fn color_along(&self, r: &Ray, remaining: usize, branch: bool) -> Color {
         let xs = self.intersect(r);
 
         if let Some(hit) = xs.hit() {
-            let comps = hit.prepare_computations(r);
+            let comps = hit.prepare_computations(r, &xs);
+
+            self.shade(&comps, remaining, branch)
+        } else {
+            self.background.color_at(&r.direction)
+        }
+    }
+
+    fn shade(&self, comps: &Computations, remaining: usize, branch: bool) -> Color {
+        let material = &comps.obj.material;
+        let color = material.pattern.color_at_object(comps.obj, &comps.over_point);
+        let surface = self.lights.iter().fold(material.emissive, |acc, light| {
+            acc + comps.shading.lighting(
+                color,
+                light,
+                comps.eyev,
+                comps.normalv,
+                &self.visible_samples(light, &comps.over_point),
+            )
+        });
+
+        let reflected = self.reflected(comps, remaining, branch);
+        let refracted = self.refracted(comps, remaining, branch);
 
-            self.shade_hit(&comps)
+        let shading = &comps.shading;
+        if shading.reflective > 0.0 && shading.transparency > 0.0 {
+            let reflectance = comps.schlick();
+            surface + reflected * reflectance + refracted * (1.0 - reflectance)
         } else {
+            surface + reflected + refracted
+        }
+    }
+
+    fn reflected(&self, comps: &Computations, remaining: usize, branch: bool) -> Color {
+        let material = &comps.obj.material;
+        let color = material.pattern.color_at_object(comps.obj, &comps.over_point);
+        let weight = comps.shading.reflection_weight(color, &comps.eyev, &comps.normalv);
+        if remaining == 0 || weight.is_black() {
+            return Color::black();
+        }
+
+        let roughness = comps.shading.roughness;
+        if roughness <= 0.0 {
+            let reflect_ray = Ray::new(comps.over_point, comps.reflectv);
+            return self.color_along(&reflect_ray, remaining - 1, branch) * weight;
+        }
+
+        let samples = if branch { material.glossy_samples } else { 1 };
+        let color = glossy_average(samples, || {
+            let reflect_ray = Ray::new(comps.over_point, comps.glossy_reflectv(roughness));
+            self.color_along(&reflect_ray, remaining - 1, false)
+        });
+        color * weight
+    }
+
+    fn refracted(&self, comps: &Computations, remaining: usize, branch: bool) -> Color {
+        let transparency = comps.shading.transparency;
+        if remaining == 0 || transparency == 0.0 {
+            return Color::black();
+        }
+
+        let roughness = comps.shading.roughness;
+        if roughness <= 0.0 {
+            return match comps.refractv() {
+                Some(direction) => {
+                    let refract_ray = Ray::new(comps.under_point, direction);
+                    self.color_along(&refract_ray, remaining - 1, branch) * transparency
+                }
+                None => Color::black(),
+            };
+        }
+
+        let samples = if branch { comps.obj.material.glossy_samples } else { 1 };
+        let color = glossy_average(samples, || match comps.glossy_refractv(roughness) {
+            Some(direction) => {
+                let refract_ray = Ray::new(comps.under_point, direction);
+                self.color_along(&refract_ray, remaining - 1, false)
+            }
+            None => Color::black(),
+        });
+        color * transparency
+    }
+
+    // A single Monte Carlo estimate of the light arriving along `r`. Rather than following
+    // every reflected, refracted and diffuse ray the way color_at does, one of them is picked
+    // at random in proportion to how much it contributes, so that averaging many paths
+    // converges on the full image including light bounced between diffuse surfaces
+    pub fn trace_path(&self, r: &Ray, depth: usize, max_depth: usize) -> Color {
+        self.trace(r, depth, max_depth, self.emitters(), true)
+    }
+
+    // Emission seen after a diffuse bounce is left out when the emitter was already sampled
+    // directly at the previous vertex, so that it isn't counted twice
+    fn trace(
+        &self,
+        r: &Ray,
+        depth: usize,
+        max_depth: usize,
+        emitters: &[Emitter],
+        count_emission: bool,
+    ) -> Color {
+        if depth > max_depth {
+            return Color::black();
+        }
+
+        let xs = self.intersect(r);
+        let hit = match xs.hit() {
+            Some(hit) => hit,
+            None => return self.path_background(r, count_emission),
+        };
+
+        let comps = hit.prepare_computations(r, &xs);
+        let material = &comps.obj.material;
+        let shading = &comps.shading;
+
+        let emitted = if count_emission || !self.is_sampled_emitter(comps.obj, emitters) {
+            material.emissive
+        } else {
+            Color::black()
+        };
+
+        let choice = rand::random::<FP>();
+        if choice < shading.reflective {
+            let reflectv = comps.glossy_reflectv(shading.roughness);
+            let reflect_ray = Ray::new(comps.over_point, reflectv);
+            return emitted + self.trace(&reflect_ray, depth + 1, max_depth, emitters, true);
+        }
+        if choice < shading.reflective + shading.transparency {
+            let ray = match comps.glossy_refractv(shading.roughness) {
+                Some(direction) if rand::random::<FP>() >= comps.schlick() => {
+                    Ray::new(comps.under_point, direction)
+                }
+                _ => Ray::new(comps.over_point, comps.glossy_reflectv(shading.roughness)),
+            };
+            return emitted + self.trace(&ray, depth + 1, max_depth, emitters, true);
+        }
+
+        let color = material.pattern.color_at_object(comps.obj, &comps.over_point);
+        let lobes = |lightv: &Tuple| match shading.model {
+            ShadingModel::Phong => (
+                color * shading.diffuse * lightv.dot(&comps.normalv),
+                Color::black(),
+            ),
+            ShadingModel::CookTorrance => {
+                shading.reflectance_lobes(color, &comps.eyev, &comps.normalv, lightv)
+            }
+        };
+        let reflectance = |lightv: &Tuple| {
+            let (diffuse, glossy) = lobes(lightv);
+            diffuse + glossy
+        };
+        let diffuse = |lightv: &Tuple| lobes(lightv).0;
+        let direct = emitted
+            + self.direct_lighting(&comps, &reflectance, &diffuse)
+            + self.emitted_lighting(&comps, &diffuse, emitters);
+
+        let (bounce, weight, glossy) = match shading.model {
+            ShadingModel::Phong => (
+                cosine_weighted_direction(&comps.normalv),
+                color * shading.diffuse,
+                false,
+            ),
+            ShadingModel::CookTorrance => match cook_torrance_bounce(&comps, color) {
+                Some(bounce) => bounce,
+                None => return direct,
+            },
+        };
+
+        // Dim paths are ended at random, with the survivors weighted up to make up for them
+        let mut survival = 1.0;
+        if depth >= ROULETTE_DEPTH {
+            survival = weight.red.max(weight.green).max(weight.blue).clamp(0.05, 0.95);
+            if rand::random::<FP>() >= survival {
+                return direct;
+            }
+        }
+
+        let bounce = Ray::new(comps.over_point, bounce);
+        let indirect = self.trace(&bounce, depth + 1, max_depth, emitters, glossy);
+        direct + weight * indirect * (1.0 / survival)
+    }
+
+    // Light sampled directly from every light (next event estimation), using the same samples
+    // for both shading and shadow rays. Paths can't hit point, area, spot or directional
+    // lights, so those light every lobe here. An environment light, like an emissive shape,
+    // only lights the diffuse lobe, since glossy bounces find it themselves
+    fn direct_lighting(
+        &self,
+        comps: &Computations,
+        reflectance: &dyn Fn(&Tuple) -> Color,
+        diffuse: &dyn Fn(&Tuple) -> Color,
+    ) -> Color {
+        self.lights.iter().fold(Color::black(), |acc, light| {
+            let reflectance = match light.light {
+                Lights::Environment(_) => diffuse,
+                _ => reflectance,
+            };
+            let samples = light.samples(&comps.over_point);
+            let sum = samples
+                .iter()
+                .filter(|(lightv, distance)| {
+                    lightv.dot(&comps.normalv) > 0.0
+                        && !self.is_shadowed(&comps.over_point, lightv, *distance)
+                })
+                .fold(Color::black(), |sum, (lightv, distance)| {
+                    sum + reflectance(lightv) * light.intensity_from(lightv, *distance)
+                });
+
+            acc + sum * (1.0 / samples.len().max(1) as FP)
+        })
+    }
+
+    // One sample towards each emissive shape. Directions are picked within the cone around the
+    // emitter's bounding sphere, and only count when the emitter is what they actually hit
+    fn emitted_lighting(
+        &self,
+        comps: &Computations,
+        reflectance: &dyn Fn(&Tuple) -> Color,
+        emitters: &[Emitter],
+    ) -> Color {
+        emitters.iter().fold(Color::black(), |acc, e| {
+            let to_center = e.center - comps.over_point;
+            let distance = to_center.magnitude();
+
+            let (direction, pdf) = if distance <= e.radius {
+                (uniform_sphere_direction(), 1.0 / (4.0 * PI))
+            } else {
+                let sin_max = e.radius / distance;
+                let cos_max = (1.0 - sin_max * sin_max).sqrt();
+                uniform_cone_direction(&(to_center / distance), cos_max)
+            };
+
+            if direction.dot(&comps.normalv) <= 0.0 {
+                return acc;
+            }
+
+            let r = Ray::new(comps.over_point, direction);
+            match self.intersect(&r).hit() {
+                Some(hit) if hit.obj.get_id() == e.id => {
+                    acc + reflectance(&direction) * hit.obj.material.emissive * (1.0 / (PI * pdf))
+                }
+                _ => acc,
+            }
+        })
+    }
+
+    // After a diffuse bounce, an environment light has already accounted for what the path
+    // would see of the background, so it isn't counted a second time. Mirror and glass
+    // bounces don't sample lights, so they still see it
+    fn path_background(&self, r: &Ray, count_emission: bool) -> Color {
+        let lit_by_environment = self
+            .lights
+            .iter()
+            .any(|l| matches!(l.light, Lights::Environment(_)));
+
+        if !count_emission && lit_by_environment {
             Color::black()
+        } else {
+            self.background.color_at(&r.direction)
         }
     }
 
-    pub fn is_shadowed(&self, p: &Tuple) -> bool {
-        let v = self.light.unwrap().position - *p;
-        let distance = v.magnitude();
-        let direction = v.normalize();
+    // The fraction of the samples on the light that can be seen from `p`, 0.0 being fully in
+    // shadow and 1.0 fully lit
+    pub fn intensity_at(&self, light: &Light, p: &Tuple) -> FP {
+        let samples = self.visible_samples(light, p);
+        let visible: FP = samples.iter().map(|(_, _, visibility)| visibility).sum();
+
+        visible / samples.len().max(1) as FP
+    }
+
+    // Each sample on the light seen from `p`, with 1.0 if it's in view and 0.0 if it's in
+    // shadow. Area and environment lights pick fresh samples every time, so shading has to
+    // use these same ones for its shadows to line up
+    pub fn visible_samples(&self, light: &Light, p: &Tuple) -> Vec<(Tuple, FP, FP)> {
+        light
+            .samples(p)
+            .into_iter()
+            .map(|(lightv, distance)| {
+                let visibility = if self.is_shadowed(p, &lightv, distance) {
+                    0.0
+                } else {
+                    1.0
+                };
+                (lightv, distance, visibility)
+            })
+            .collect()
+    }
 
-        let r = Ray::new(*p, direction);
+    pub fn is_shadowed(&self, p: &Tuple, direction: &Tuple, distance: FP) -> bool {
+        let r = Ray::new(*p, *direction);
 
         if let Some(hit) = self.intersect(&r).hit() {
             hit.t < distance
@@ -91,15 +454,99 @@ impl World {
     }
 }
 
+fn glossy_average(samples: usize, sample: impl Fn() -> Color) -> Color {
+    let samples = samples.max(1);
+    (0..samples).fold(Color::black(), |acc, _| acc + sample()) * (1.0 / samples as FP)
+}
+
+// Picks between the glossy and diffuse lobes of a Cook-Torrance surface in proportion to how
+// much each reflects, returning the new direction, the weight of light arriving along it and
+// whether it was the glossy lobe
+fn cook_torrance_bounce(comps: &Computations, color: Color) -> Option<(Tuple, Color, bool)> {
+    let shading = &comps.shading;
+    let (eyev, normalv) = (comps.eyev, comps.normalv);
+    let n_dot_v = eyev.dot(&normalv);
+    if n_dot_v <= 0.0 {
+        return None;
+    }
+
+    let f0 = shading.specular_f0(color);
+    let glossy = luminance(&fresnel_schlick(f0, n_dot_v));
+    let diffuse = luminance(&(color * (1.0 - shading.metallic)));
+    let p_glossy = if glossy + diffuse > 0.0 {
+        (glossy / (glossy + diffuse)).clamp(0.05, 1.0)
+    } else {
+        1.0
+    };
+
+    if rand::random::<FP>() < p_glossy {
+        let alpha = alpha(shading.roughness);
+        let halfv = ggx_half_vector(&normalv, alpha);
+        let lightv = (-eyev).reflect(&halfv);
+        let n_dot_l = lightv.dot(&normalv);
+        let v_dot_h = eyev.dot(&halfv);
+        if n_dot_l <= 0.0 || v_dot_h <= 0.0 {
+            return None;
+        }
+
+        // The GGX distribution cancels with the pdf of the half vector it was sampled from
+        let weight = fresnel_schlick(f0, v_dot_h)
+            * (smith_geometry(n_dot_v, n_dot_l, alpha) * v_dot_h
+                / (n_dot_v * normalv.dot(&halfv) * p_glossy));
+        Some((lightv, weight, true))
+    } else {
+        let lightv = cosine_weighted_direction(&normalv);
+        let halfv = (lightv + eyev).normalize();
+        let fresnel = fresnel_schlick(f0, eyev.dot(&halfv));
+        let weight =
+            (Color::white() - fresnel) * color * ((1.0 - shading.metallic) / (1.0 - p_glossy));
+        Some((lightv, weight, false))
+    }
+}
+
+// Unbounded emitters such as planes can't be aimed at, so they're left for paths to find
+fn collect_emitters(shape: &Shape, to_world: Matrix4, emitters: &mut Vec<Emitter>) {
+    match &shape.shape {
+        Shapes::Group(g) => {
+            for c in g.children.iter() {
+                collect_emitters(c, to_world * shape.transform(), emitters);
+            }
+        }
+        Shapes::Csg(c) => {
+            collect_emitters(&c.left, to_world * shape.transform(), emitters);
+            collect_emitters(&c.right, to_world * shape.transform(), emitters);
+        }
+        _ => {
+            let bounds: BoundingBox = shape.bounds().transform(to_world);
+            if shape.material.emissive.is_black() || !bounds.is_finite() {
+                return;
+            }
+
+            let center = bounds.centroid();
+            emitters.push(Emitter {
+                id: shape.get_id(),
+                center,
+                radius: (bounds.max - center).magnitude(),
+            });
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use crate::canvas::Canvas;
+    use crate::material::{Material, Parameter};
+    use crate::light::Lights;
     use crate::world::*;
+    use crate::PI;
+
+    const MAX_DEPTH: usize = 5;
 
     #[test]
     fn a_whole_new_world() {
         let w = World::new();
-        assert_eq!(None, w.light);
-        assert_eq!(0, w.objects.len());
+        assert!(w.lights.is_empty());
+        assert_eq!(0, w.objects().len());
     }
 
     #[test]
@@ -107,13 +554,13 @@ mod tests {
         let w = World::default();
 
         assert_eq!(
-            Some(PointLight::new(
+            vec![Light::point(
                 point(-10.0, 10.0, -10.0),
                 Color::new(1.0, 1.0, 1.0)
-            )),
-            w.light
+            )],
+            w.lights
         );
-        assert_eq!(2, w.objects.len());
+        assert_eq!(2, w.objects().len());
     }
 
     #[test]
@@ -130,14 +577,58 @@ mod tests {
         assert_eq!(6.0, xs[3].t);
     }
 
+    #[test]
+    fn intersect_world_with_bvh() {
+        let mut w = World::default();
+        w.build_bvh();
+        let r = Ray::new(point(0.0, 0.0, -5.0), vector(0.0, 0.0, 1.0));
+
+        let xs = w.intersect(&r);
+        let expected = w.intersect_all(&r);
+
+        assert_eq!(4, xs.len());
+        for i in 0..4 {
+            assert_eq!(expected[i].t, xs[i].t);
+            assert_eq!(expected[i].obj.get_id(), xs[i].obj.get_id());
+        }
+    }
+
+    #[test]
+    fn bvh_follows_changes_to_objects() {
+        let mut w = World::default();
+        w.build_bvh();
+        let r = Ray::new(point(0.0, 0.0, -5.0), vector(0.0, 0.0, 1.0));
+        assert_eq!(4, w.intersect(&r).len());
+
+        w.objects_mut()[0].set_transform(translation(0.0, 5.0, 0.0));
+        let xs = w.intersect(&r);
+        assert_eq!(2, xs.len());
+        assert_eq!(4.5, xs[0].t);
+
+        w.add(Shape::plane());
+        w.objects_mut()[2].set_transform(rotation_x(PI / 2.0));
+        assert_eq!(3, w.intersect(&r).len());
+    }
+
+    #[test]
+    fn shadows_with_bvh() {
+        let mut w = World::default();
+        w.build_bvh();
+
+        assert_eq!(1.0, w.intensity_at(&w.lights[0], &point(0.0, 10.0, 0.0)));
+        assert_eq!(0.0, w.intensity_at(&w.lights[0], &point(10.0, -10.0, 10.0)));
+        assert_eq!(1.0, w.intensity_at(&w.lights[0], &point(-20.0, 20.0, -20.0)));
+        assert_eq!(1.0, w.intensity_at(&w.lights[0], &point(-2.0, 2.0, -2.0)));
+    }
+
     #[test]
     fn shading_an_intersection() {
         let w = World::default();
         let r = Ray::new(point(0.0, 0.0, -5.0), vector(0.0, 0.0, 1.0));
-        let s = w.objects[0].clone();
-        let i = Intersection::new(4.0, s);
-        let comps = i.prepare_computations(&r);
-        let c = w.shade_hit(&comps);
+        let s = w.objects()[0].clone();
+        let i = Intersection::new(4.0, &s);
+        let comps = i.prepare_computations(&r, &Intersections::new(vec![i]));
+        let c = w.shade_hit(&comps, MAX_DEPTH);
 
         assert_eq!(c, Color::new(0.38066, 0.47583, 0.2855));
     }
@@ -145,12 +636,12 @@ mod tests {
     #[test]
     fn shading_an_intersection_inside() {
         let mut w = World::default();
-        w.light = Some(PointLight::new(point(0.0, 0.25, 0.0), Color::white()));
+        w.lights = vec![Light::point(point(0.0, 0.25, 0.0), Color::white())];
         let r = Ray::new(point(0.0, 0.0, 0.0), vector(0.0, 0.0, 1.0));
-        let s = w.objects[1].clone();
-        let i = Intersection::new(0.5, s);
-        let comps = i.prepare_computations(&r);
-        let c = w.shade_hit(&comps);
+        let s = w.objects()[1].clone();
+        let i = Intersection::new(0.5, &s);
+        let comps = i.prepare_computations(&r, &Intersections::new(vec![i]));
+        let c = w.shade_hit(&comps, MAX_DEPTH);
 
         assert_eq!(c, Color::new(0.90498, 0.90498, 0.90498));
     }
@@ -159,7 +650,7 @@ mod tests {
     fn shading_miss() {
         let w = World::default();
         let r = Ray::new(point(0.0, 0.0, -5.0), vector(0.0, 1.0, 0.0));
-        let c = w.color_at(&r);
+        let c = w.color_at(&r, MAX_DEPTH);
 
         assert_eq!(c, Color::black());
     }
@@ -168,7 +659,7 @@ mod tests {
     fn shading_hit() {
         let w = World::default();
         let r = Ray::new(point(0.0, 0.0, -5.0), vector(0.0, 0.0, 1.0));
-        let c = w.color_at(&r);
+        let c = w.color_at(&r, MAX_DEPTH);
 
         assert_eq!(c, Color::new(0.38066, 0.47583, 0.2855));
     }
@@ -176,60 +667,743 @@ mod tests {
     #[test]
     fn shading_hit_shadowed() {
         let mut w = World::new();
-        w.light = Some(PointLight::new(point(0.0, 0.0, -10.0), Color::white()));
+        w.lights = vec![Light::point(point(0.0, 0.0, -10.0), Color::white())];
 
         let s1 = Shape::sphere();
         w.add(s1);
 
         let mut s2 = Shape::sphere();
-        s2.transform = translation(0.0, 0.0, 10.0);
+        s2.set_transform(translation(0.0, 0.0, 10.0));
         w.add(s2.clone());
 
         let r = Ray::new(point(0.0, 0.0, 5.0), vector(0.0, 0.0, 1.0));
-        let i = Intersection::new(4.0, s2);
-        let comps = i.prepare_computations(&r);
+        let i = Intersection::new(4.0, &s2);
+        let comps = i.prepare_computations(&r, &Intersections::new(vec![i]));
 
-        let c = w.shade_hit(&comps);
+        let c = w.shade_hit(&comps, MAX_DEPTH);
         assert_eq!(c, Color::new(0.1, 0.1, 0.1));
     }
 
     #[test]
     fn shading_behind() {
         let mut w = World::default();
-        w.objects[0].material.ambient = 1.0;
-        w.objects[1].material.ambient = 1.0;
+        w.objects_mut()[0].material.ambient = 1.0;
+        w.objects_mut()[1].material.ambient = 1.0;
 
         let r = Ray::new(point(0.0, 0.0, 0.75), vector(0.0, 0.0, -1.0));
-        let c = w.color_at(&r);
+        let c = w.color_at(&r, MAX_DEPTH);
 
-        assert_eq!(c, w.objects[1].material.pattern.color_at(&point(0.0, 0.0, 0.0)));
+        assert_eq!(c, w.objects()[1].material.pattern.color_at(&point(0.0, 0.0, 0.0)));
     }
 
     #[test]
     fn shadow_nothing_collinear() {
         let w = World::default();
         let p = point(0.0, 10.0, 0.0);
-        assert!(!w.is_shadowed(&p));
+        assert_eq!(1.0, w.intensity_at(&w.lights[0], &p));
     }
 
     #[test]
     fn shadow_obj_between_light_and_point() {
         let w = World::default();
         let p = point(10.0, -10.0, 10.0);
-        assert!(w.is_shadowed(&p));
+        assert_eq!(0.0, w.intensity_at(&w.lights[0], &p));
     }
 
     #[test]
     fn shadow_obj_behind_light() {
         let w = World::default();
         let p = point(-20.0, 20.0, -20.0);
-        assert!(!w.is_shadowed(&p));
+        assert_eq!(1.0, w.intensity_at(&w.lights[0], &p));
     }
 
     #[test]
     fn shadow_obj_behind_point() {
         let w = World::default();
         let p = point(-2.0, 2.0, -2.0);
-        assert!(!w.is_shadowed(&p));
+        assert_eq!(1.0, w.intensity_at(&w.lights[0], &p));
+    }
+
+    #[test]
+    fn reflected_color_for_nonreflective_material() {
+        let mut w = World::default();
+        w.objects_mut()[1].material.ambient = 1.0;
+        let r = Ray::new(point(0.0, 0.0, 0.0), vector(0.0, 0.0, 1.0));
+        let i = Intersection::new(1.0, &w.objects()[1]);
+        let comps = i.prepare_computations(&r, &Intersections::new(vec![i]));
+
+        assert_eq!(Color::black(), w.reflected_color(&comps, MAX_DEPTH));
+    }
+
+    fn world_with_reflective_plane() -> World {
+        let mut w = World::default();
+        let mut p = Shape::plane();
+        p.material.reflective = 0.5;
+        p.set_transform(translation(0.0, -1.0, 0.0));
+        w.add(p);
+        w
+    }
+
+    #[test]
+    fn reflected_color_for_reflective_material() {
+        let w = world_with_reflective_plane();
+        let v = (2.0 as FP).sqrt() / 2.0;
+        let r = Ray::new(point(0.0, 0.0, -3.0), vector(0.0, -v, v));
+        let i = Intersection::new((2.0 as FP).sqrt(), &w.objects()[2]);
+        let comps = i.prepare_computations(&r, &Intersections::new(vec![i]));
+
+        assert_eq!(
+            Color::new(0.19033, 0.23791, 0.14274),
+            w.reflected_color(&comps, MAX_DEPTH)
+        );
+    }
+
+    #[test]
+    fn shade_hit_with_reflective_material() {
+        let w = world_with_reflective_plane();
+        let v = (2.0 as FP).sqrt() / 2.0;
+        let r = Ray::new(point(0.0, 0.0, -3.0), vector(0.0, -v, v));
+        let i = Intersection::new((2.0 as FP).sqrt(), &w.objects()[2]);
+        let comps = i.prepare_computations(&r, &Intersections::new(vec![i]));
+
+        assert_eq!(
+            Color::new(0.87676, 0.92434, 0.82917),
+            w.shade_hit(&comps, MAX_DEPTH)
+        );
+    }
+
+    #[test]
+    fn color_at_with_mutually_reflective_surfaces() {
+        let mut w = World::new();
+        w.lights = vec![Light::point(point(0.0, 0.0, 0.0), Color::white())];
+
+        let mut lower = Shape::plane();
+        lower.material.reflective = 1.0;
+        lower.set_transform(translation(0.0, -1.0, 0.0));
+        w.add(lower);
+
+        let mut upper = Shape::plane();
+        upper.material.reflective = 1.0;
+        upper.set_transform(translation(0.0, 1.0, 0.0));
+        w.add(upper);
+
+        let r = Ray::new(point(0.0, 0.0, 0.0), vector(0.0, 1.0, 0.0));
+        let c = w.color_at(&r, MAX_DEPTH);
+
+        assert!(c.red.is_finite());
+    }
+
+    #[test]
+    fn reflected_color_at_maximum_recursive_depth() {
+        let w = world_with_reflective_plane();
+        let v = (2.0 as FP).sqrt() / 2.0;
+        let r = Ray::new(point(0.0, 0.0, -3.0), vector(0.0, -v, v));
+        let i = Intersection::new((2.0 as FP).sqrt(), &w.objects()[2]);
+        let comps = i.prepare_computations(&r, &Intersections::new(vec![i]));
+
+        assert_eq!(Color::black(), w.reflected_color(&comps, 0));
+    }
+
+    #[test]
+    fn refracted_color_with_opaque_surface() {
+        let w = World::default();
+        let r = Ray::new(point(0.0, 0.0, -5.0), vector(0.0, 0.0, 1.0));
+        let xs = Intersections::new(vec![
+            Intersection::new(4.0, &w.objects()[0]),
+            Intersection::new(6.0, &w.objects()[0]),
+        ]);
+        let comps = xs[0].prepare_computations(&r, &xs);
+
+        assert_eq!(Color::black(), w.refracted_color(&comps, MAX_DEPTH));
+    }
+
+    #[test]
+    fn refracted_color_at_maximum_recursive_depth() {
+        let mut w = World::default();
+        w.objects_mut()[0].material.transparency = 1.0;
+        w.objects_mut()[0].material.refractive_index = 1.5;
+        let r = Ray::new(point(0.0, 0.0, -5.0), vector(0.0, 0.0, 1.0));
+        let xs = Intersections::new(vec![
+            Intersection::new(4.0, &w.objects()[0]),
+            Intersection::new(6.0, &w.objects()[0]),
+        ]);
+        let comps = xs[0].prepare_computations(&r, &xs);
+
+        assert_eq!(Color::black(), w.refracted_color(&comps, 0));
+    }
+
+    #[test]
+    fn refracted_color_under_total_internal_reflection() {
+        let mut w = World::default();
+        w.objects_mut()[0].material.transparency = 1.0;
+        w.objects_mut()[0].material.refractive_index = 1.5;
+        let v = (2.0 as FP).sqrt() / 2.0;
+        let r = Ray::new(point(0.0, 0.0, v), vector(0.0, 1.0, 0.0));
+        let xs = Intersections::new(vec![
+            Intersection::new(-v, &w.objects()[0]),
+            Intersection::new(v, &w.objects()[0]),
+        ]);
+        let comps = xs[1].prepare_computations(&r, &xs);
+
+        assert_eq!(Color::black(), w.refracted_color(&comps, MAX_DEPTH));
+    }
+
+    fn world_with_glass_floor_and_ball(reflective: FP) -> World {
+        let mut w = World::default();
+
+        let mut floor = Shape::plane();
+        floor.set_transform(translation(0.0, -1.0, 0.0));
+        floor.material.reflective = reflective;
+        floor.material.transparency = 0.5;
+        floor.material.refractive_index = 1.5;
+        w.add(floor);
+
+        let mut ball = Shape::sphere();
+        ball.material.pattern = Pattern::solid(Color::new(1.0, 0.0, 0.0));
+        ball.material.ambient = 0.5;
+        ball.set_transform(translation(0.0, -3.5, -0.5));
+        w.add(ball);
+
+        w
+    }
+
+    #[test]
+    fn shade_hit_with_transparent_material() {
+        let w = world_with_glass_floor_and_ball(0.0);
+        let v = (2.0 as FP).sqrt() / 2.0;
+        let r = Ray::new(point(0.0, 0.0, -3.0), vector(0.0, -v, v));
+        let xs = Intersections::new(vec![Intersection::new((2.0 as FP).sqrt(), &w.objects()[2])]);
+        let comps = xs[0].prepare_computations(&r, &xs);
+
+        assert_eq!(
+            Color::new(0.93642, 0.68642, 0.68642),
+            w.shade_hit(&comps, MAX_DEPTH)
+        );
+    }
+
+    #[test]
+    fn shade_hit_with_reflective_transparent_material() {
+        let w = world_with_glass_floor_and_ball(0.5);
+        let v = (2.0 as FP).sqrt() / 2.0;
+        let r = Ray::new(point(0.0, 0.0, -3.0), vector(0.0, -v, v));
+        let xs = Intersections::new(vec![Intersection::new((2.0 as FP).sqrt(), &w.objects()[2])]);
+        let comps = xs[0].prepare_computations(&r, &xs);
+
+        assert_eq!(
+            Color::new(0.93391, 0.69643, 0.69243),
+            w.shade_hit(&comps, MAX_DEPTH)
+        );
+    }
+
+    #[test]
+    fn shading_hit_sums_every_light() {
+        let mut w = World::default();
+        w.lights.push(w.lights[0].clone());
+        let r = Ray::new(point(0.0, 0.0, -5.0), vector(0.0, 0.0, 1.0));
+        let c = w.color_at(&r, MAX_DEPTH);
+
+        assert_eq!(c, Color::new(0.76132, 0.95166, 0.5710));
+    }
+
+    #[test]
+    fn shadows_are_tested_per_light() {
+        let mut w = World::default();
+        w.lights.push(Light::point(point(20.0, -20.0, 20.0), Color::white()));
+        let p = point(10.0, -10.0, 10.0);
+
+        assert_eq!(0.0, w.intensity_at(&w.lights[0], &p));
+        assert_eq!(1.0, w.intensity_at(&w.lights[1], &p));
+    }
+
+    #[test]
+    fn area_light_intensity_is_fraction_of_visible_samples() {
+        let w = World::default();
+        let mut light = Light::area(
+            point(-0.5, -0.5, -5.0),
+            vector(1.0, 0.0, 0.0),
+            2,
+            vector(0.0, 1.0, 0.0),
+            2,
+            Color::white(),
+        );
+        if let Lights::Area(l) = &mut light.light {
+            l.jitter = false;
+        }
+
+        let cases = [
+            (point(0.0, 0.0, 2.0), 0.0),
+            (point(1.0, -1.0, 2.0), 0.25),
+            (point(1.5, 0.0, 2.0), 0.5),
+            (point(1.25, 1.25, 3.0), 0.75),
+            (point(0.0, 0.0, -2.0), 1.0),
+        ];
+
+        for (p, result) in cases {
+            assert_eq!(result, w.intensity_at(&light, &p));
+        }
+    }
+
+    #[test]
+    fn shading_uses_the_samples_shadows_were_tested_with() {
+        // The floor hides exactly the half of a uniform sky that can't light it anyway, so
+        // counting that half as shadow as well would leave the floor half as bright
+        let mut map = Canvas::new(64, 32);
+        map.fill(Color::white());
+        let mut w = World::new();
+        w.lights.push(Light::environment(map, 4000, Color::white()));
+        let mut floor = Shape::plane();
+        floor.material.ambient = 0.0;
+        floor.material.diffuse = 1.0;
+        floor.material.specular = 0.0;
+        w.add(floor);
+
+        let r = Ray::new(point(0.0, 1.0, -1.0), vector(0.0, -1.0, 1.0).normalize());
+        let c = w.color_at(&r, MAX_DEPTH);
+        assert!((1.0 - c.red).abs() < 0.1);
+    }
+
+    #[test]
+    fn lights_without_samples_give_no_light() {
+        let mut w = World::default();
+        let mut map = Canvas::new(8, 4);
+        map.fill(Color::white());
+        w.lights = vec![
+            Light::environment(map, 0, Color::white()),
+            Light::area(
+                point(-1.0, 5.0, -1.0),
+                vector(2.0, 0.0, 0.0),
+                0,
+                vector(0.0, 0.0, 2.0),
+                4,
+                Color::white(),
+            ),
+        ];
+        let p = point(0.0, 2.0, 0.0);
+        let r = Ray::new(point(0.0, 0.0, -5.0), vector(0.0, 0.0, 1.0));
+
+        for light in w.lights.iter() {
+            assert_eq!(0.0, w.intensity_at(light, &p));
+        }
+        let c = w.trace_path(&r, 0, MAX_DEPTH);
+        assert!(c.red.is_finite() && c.green.is_finite() && c.blue.is_finite());
+    }
+
+    #[test]
+    fn spot_light_casts_shadows() {
+        let w = World::default();
+        let light = Light::spot(
+            point(0.0, 0.0, -10.0),
+            vector(0.0, 0.0, 1.0),
+            PI / 8.0,
+            PI / 4.0,
+            Color::white(),
+        );
+
+        assert_eq!(0.0, w.intensity_at(&light, &point(0.0, 0.0, 5.0)));
+        assert_eq!(1.0, w.intensity_at(&light, &point(0.0, 0.0, -5.0)));
+    }
+
+    #[test]
+    fn directional_light_shadows_are_unbounded() {
+        let mut w = World::default();
+        let light = Light::directional(vector(0.0, -1.0, 0.0), Color::white());
+
+        assert_eq!(0.0, w.intensity_at(&light, &point(0.0, -10.0, 0.0)));
+        assert_eq!(1.0, w.intensity_at(&light, &point(3.0, -10.0, 0.0)));
+
+        let mut far = Shape::sphere();
+        far.set_transform(translation(3.0, 1000.0, 0.0));
+        w.add(far);
+        assert_eq!(0.0, w.intensity_at(&light, &point(3.0, -10.0, 0.0)));
+    }
+
+    #[test]
+    fn shading_with_directional_light() {
+        let mut w = World::default();
+        w.lights = vec![Light::directional(vector(0.0, 0.0, 1.0), Color::white())];
+        let r = Ray::new(point(0.0, 0.0, -5.0), vector(0.0, 0.0, 1.0));
+        let c = w.color_at(&r, MAX_DEPTH);
+
+        assert_eq!(Color::new(0.84, 1.0, 0.68), c);
+    }
+
+    #[test]
+    fn missed_rays_see_background() {
+        let mut w = World::default();
+        w.background = Background::Gradient {
+            top: Color::white(),
+            bottom: Color::black(),
+        };
+        let r = Ray::new(point(0.0, 0.0, -5.0), vector(0.0, 1.0, 0.0));
+
+        assert_eq!(Color::white(), w.color_at(&r, MAX_DEPTH));
+    }
+
+    #[test]
+    fn reflected_rays_see_background() {
+        let mut w = World::new();
+        w.background = Background::Color(Color::blue());
+        let mut mirror = Shape::plane();
+        mirror.material.reflective = 1.0;
+        mirror.material.ambient = 0.0;
+        mirror.material.diffuse = 0.0;
+        w.add(mirror);
+
+        let r = Ray::new(point(0.0, 1.0, 0.0), vector(0.0, -1.0, 0.0));
+        assert_eq!(Color::blue(), w.color_at(&r, MAX_DEPTH));
+        assert_eq!(Color::black(), w.color_at(&r, 0));
+    }
+
+    #[test]
+    fn path_that_misses_sees_background() {
+        let mut w = World::new();
+        w.background = Background::Color(Color::blue());
+        let r = Ray::new(point(0.0, 0.0, -5.0), vector(0.0, 0.0, 1.0));
+
+        assert_eq!(Color::blue(), w.trace_path(&r, 0, MAX_DEPTH));
+    }
+
+    #[test]
+    fn path_traced_direct_lighting() {
+        let mut w = World::new();
+        w.lights = vec![Light::point(point(0.0, 10.0, 0.0), Color::white())];
+        let mut floor = Shape::plane();
+        floor.material.diffuse = 0.5;
+        w.add(floor);
+
+        let r = Ray::new(point(0.0, 1.0, -1.0), vector(0.0, -1.0, 1.0).normalize());
+        assert_eq!(Color::new(0.5, 0.5, 0.5), w.trace_path(&r, 0, MAX_DEPTH));
+    }
+
+    #[test]
+    fn white_furnace_conserves_energy() {
+        let mut w = World::new();
+        w.background = Background::Color(Color::white());
+        let mut s = Shape::sphere();
+        s.material.diffuse = 0.5;
+        w.add(s);
+
+        let r = Ray::new(point(0.0, 0.0, -5.0), vector(0.0, 0.0, 1.0));
+        for _ in 0..10 {
+            assert_eq!(Color::new(0.5, 0.5, 0.5), w.trace_path(&r, 0, MAX_DEPTH));
+        }
+    }
+
+    #[test]
+    fn path_tracing_picks_up_indirect_light() {
+        let mut w = World::new();
+        w.lights = vec![Light::directional(vector(0.0, -1.0, 0.0), Color::white())];
+        let mut floor = Shape::plane();
+        floor.material.diffuse = 1.0;
+        w.add(floor);
+        let mut wall = Shape::plane();
+        wall.set_transform(translation(0.0, 0.0, 1.0) * rotation_x(PI / 2.0));
+        w.add(wall);
+
+        // The wall is edge on to the light, so only light bounced off the floor reaches it
+        let r = Ray::new(point(0.0, 1.0, -5.0), vector(0.0, 0.0, 1.0));
+        let samples = 200;
+        let mut sum = Color::black();
+        for _ in 0..samples {
+            sum = sum + w.trace_path(&r, 0, MAX_DEPTH);
+        }
+
+        assert_eq!(Color::new(0.1, 0.1, 0.1), w.color_at(&r, MAX_DEPTH));
+        assert!(sum.red / samples as FP > 0.2);
+    }
+
+    #[test]
+    fn path_tracing_is_cut_off_at_maximum_depth() {
+        let mut w = World::new();
+        w.background = Background::Color(Color::white());
+        let r = Ray::new(point(0.0, 0.0, -5.0), vector(0.0, 0.0, 1.0));
+
+        assert_eq!(Color::black(), w.trace_path(&r, MAX_DEPTH + 1, MAX_DEPTH));
+    }
+
+    #[test]
+    fn shade_hit_adds_emission_regardless_of_lights() {
+        let mut w = World::default();
+        w.lights = vec![];
+        w.objects_mut()[0].material.emissive = Color::new(1.0, 0.5, 0.0);
+        let r = Ray::new(point(0.0, 0.0, -5.0), vector(0.0, 0.0, 1.0));
+
+        assert_eq!(Color::new(1.0, 0.5, 0.0), w.color_at(&r, MAX_DEPTH));
+    }
+
+    #[test]
+    fn finding_emitters() {
+        let mut w = World::new();
+        let mut lamp = Shape::sphere();
+        lamp.material.emissive = Color::white();
+        lamp.set_transform(scaling(2.0, 2.0, 2.0));
+        let mut g = Shape::group(vec![lamp]);
+        g.set_transform(translation(0.0, 5.0, 0.0));
+        w.add(g);
+        let mut glowing_floor = Shape::plane();
+        glowing_floor.material.emissive = Color::white();
+        w.add(glowing_floor);
+        w.add(Shape::sphere());
+
+        let emitters = w.find_emitters();
+        assert_eq!(1, emitters.len());
+        assert_eq!(point(0.0, 5.0, 0.0), emitters[0].center);
+        assert!(((12.0 as FP).sqrt() - emitters[0].radius).abs() < crate::EPSILON);
+    }
+
+    #[test]
+    fn emitters_are_found_again_after_objects_change() {
+        let lamp = |y: FP| {
+            let mut s = Shape::sphere();
+            s.material.emissive = Color::white();
+            s.set_transform(translation(0.0, y, 0.0));
+            s
+        };
+        let mut w = World::new();
+        w.add(lamp(0.0));
+        assert_eq!(1, w.emitters().len());
+
+        w.add(lamp(3.0));
+        assert_eq!(2, w.emitters().len());
+
+        w.objects_mut()[0].material.emissive = Color::black();
+        assert_eq!(1, w.emitters().len());
+        assert_eq!(point(0.0, 3.0, 0.0), w.emitters()[0].center);
+    }
+
+    #[test]
+    fn path_tracing_samples_emissive_shapes() {
+        let mut w = World::new();
+        let mut floor = Shape::plane();
+        floor.material.diffuse = 1.0;
+        w.add(floor);
+        let mut lamp = Shape::sphere();
+        lamp.material.emissive = Color::white();
+        lamp.material.diffuse = 0.0;
+        lamp.set_transform(translation(0.0, 2.0, 0.0));
+        w.add(lamp);
+        w.build_bvh();
+
+        // A sphere of radius 1 whose centre is 2 away lights the point below it by (1/2)^2
+        let r = Ray::new(point(0.0, 1.0, -1.0), vector(0.0, -1.0, 1.0).normalize());
+        let samples = 2000;
+        let mut sum = Color::black();
+        for _ in 0..samples {
+            sum = sum + w.trace_path(&r, 0, MAX_DEPTH);
+        }
+
+        assert!((0.25 - sum.red / samples as FP).abs() < 0.05);
+    }
+
+    #[test]
+    fn cook_torrance_surfaces_always_reflect() {
+        let mut w = World::new();
+        w.background = Background::Color(Color::white());
+        let mut floor = Shape::plane();
+        floor.material = Material::cook_torrance();
+        floor.material.metallic = 1.0;
+        floor.material.roughness = 0.0;
+        floor.material.pattern = Pattern::solid(Color::new(1.0, 0.5, 0.0));
+        w.add(floor);
+
+        let r = Ray::new(point(0.0, 1.0, 0.0), vector(0.0, -1.0, 0.0));
+        let xs = w.intersect(&r);
+        let comps = xs.hit().unwrap().prepare_computations(&r, &xs);
+        let c = w.reflected_color(&comps, MAX_DEPTH);
+
+        assert!(c.red > 0.95 && (c.green - 0.5).abs() < 0.05 && c.blue < 0.05);
+    }
+
+    #[test]
+    fn path_traced_rough_metal_conserves_energy() {
+        let mut w = World::new();
+        w.background = Background::Color(Color::white());
+        let mut floor = Shape::plane();
+        floor.material = Material::cook_torrance();
+        floor.material.metallic = 1.0;
+        w.add(floor);
+        w.build_bvh();
+
+        let r = Ray::new(point(0.0, 1.0, -1.0), vector(0.0, -1.0, 1.0).normalize());
+        let samples = 2000;
+        let mut sum = Color::black();
+        for _ in 0..samples {
+            sum = sum + w.trace_path(&r, 0, MAX_DEPTH);
+        }
+
+        let mean = sum.red / samples as FP;
+        assert!(mean > 0.8 && mean < 1.02);
+    }
+
+    fn world_with_mirror_under_gradient(roughness: FP) -> World {
+        let mut w = World::new();
+        w.background = Background::Gradient {
+            top: Color::white(),
+            bottom: Color::black(),
+        };
+        let mut floor = Shape::plane();
+        floor.material.reflective = 1.0;
+        floor.material.roughness = roughness;
+        w.add(floor);
+        w
+    }
+
+    #[test]
+    fn glossy_reflections_are_blurred() {
+        let r = Ray::new(point(0.0, 1.0, 0.0), vector(0.0, -1.0, 0.0));
+
+        let w = world_with_mirror_under_gradient(0.0);
+        let xs = w.intersect(&r);
+        let comps = xs.hit().unwrap().prepare_computations(&r, &xs);
+        assert_eq!(Color::white(), w.reflected_color(&comps, MAX_DEPTH));
+
+        // Jittered rays lean away from straight up and see a darker part of the gradient
+        let w = world_with_mirror_under_gradient(0.6);
+        let xs = w.intersect(&r);
+        let comps = xs.hit().unwrap().prepare_computations(&r, &xs);
+        let c = w.reflected_color(&comps, MAX_DEPTH);
+        assert!(c.red < 0.99 && c.red > 0.5);
+    }
+
+    #[test]
+    fn glossy_mirrors_facing_each_other_terminate() {
+        let mut w = World::new();
+        w.lights.push(Light::point(point(0.0, 0.0, 0.0), Color::white()));
+        let mut lower = Shape::plane();
+        lower.material.reflective = 1.0;
+        lower.material.roughness = 0.3;
+        lower.set_transform(translation(0.0, -1.0, 0.0));
+        w.add(lower);
+        let mut upper = Shape::plane();
+        upper.material.reflective = 1.0;
+        upper.material.roughness = 0.3;
+        upper.set_transform(translation(0.0, 1.0, 0.0));
+        w.add(upper);
+
+        let r = Ray::new(point(0.0, 0.0, 0.0), vector(0.0, 1.0, 0.0));
+        let c = w.color_at(&r, MAX_DEPTH);
+
+        // No bounce can add more than the ambient, diffuse and specular of one surface
+        let m = Material::new();
+        let bound = (m.ambient + m.diffuse + m.specular) * (MAX_DEPTH + 1) as FP;
+        assert!(c.red.is_finite());
+        assert!(c.red <= bound);
+    }
+
+    #[test]
+    fn glass_seen_in_rough_reflection() {
+        let mut w = World::new();
+        w.background = Background::Color(Color::white());
+        let mut floor = Shape::plane();
+        floor.material.reflective = 1.0;
+        floor.material.roughness = 0.05;
+        w.add(floor);
+        let mut glass = Shape::glass_sphere();
+        glass.set_transform(translation(0.0, 3.0, 3.0) * scaling(1.5, 1.5, 1.5));
+        w.add(glass);
+
+        // Seeing the white background through the sphere takes a bounce off the floor and
+        // two more into and out of the glass
+        let r = Ray::new(point(0.0, 1.0, -1.0), vector(0.0, -1.0, 1.0).normalize());
+        let c = w.color_at(&r, MAX_DEPTH);
+        assert!(c.red > 0.5);
+    }
+
+    #[test]
+    fn frosted_glass_blurs_what_is_behind_it() {
+        let mut w = World::new();
+        w.background = Background::Gradient {
+            top: Color::white(),
+            bottom: Color::black(),
+        };
+        let mut pane = Shape::plane();
+        pane.material.transparency = 1.0;
+        pane.material.roughness = 0.8;
+        pane.material.refractive_index = 1.5;
+        pane.set_transform(translation(0.0, 1.0, 0.0));
+        w.add(pane);
+
+        let r = Ray::new(point(0.0, 0.0, 0.0), vector(0.0, 1.0, 0.0));
+        let c = w.color_at(&r, MAX_DEPTH);
+        assert!(c.red < 0.999 && c.red > 0.5);
+    }
+
+    #[test]
+    fn reflectivity_mask_on_plane() {
+        let mut w = world_with_reflective_plane();
+        w.objects_mut()[2].material.reflective = 1.0;
+        w.objects_mut()[2].material.maps.push((
+            Parameter::Reflective,
+            Pattern::stripe(Color::white(), Color::black()),
+        ));
+        let r = Ray::new(point(0.5, 0.0, 0.0), vector(0.0, -1.0, 0.0));
+        let i = Intersection::new(1.0, &w.objects()[2]);
+        let wet = i.prepare_computations(&r, &Intersections::new(vec![i]));
+        let r = Ray::new(point(1.5, 0.0, 0.0), vector(0.0, -1.0, 0.0));
+        let dry = i.prepare_computations(&r, &Intersections::new(vec![i]));
+
+        assert_eq!(1.0, wet.shading.reflective);
+        assert_eq!(0.0, dry.shading.reflective);
+        assert_ne!(Color::black(), w.reflected_color(&wet, MAX_DEPTH));
+        assert_eq!(Color::black(), w.reflected_color(&dry, MAX_DEPTH));
+    }
+
+    #[test]
+    fn glass_with_black_refractive_index_map() {
+        let mut w = World::default();
+        let mut glass = Shape::glass_sphere();
+        glass.material.maps.push((
+            Parameter::RefractiveIndex,
+            Pattern::solid(Color::black()),
+        ));
+        w.add(glass);
+        w.objects_mut()[2].set_transform(translation(0.0, 0.0, -3.0));
+
+        let r = Ray::new(point(0.0, 0.0, -5.0), vector(0.0, 0.0, 1.0));
+        let c = w.color_at(&r, MAX_DEPTH);
+        assert!(c.red.is_finite() && c.green.is_finite() && c.blue.is_finite());
+    }
+
+    #[test]
+    fn path_traced_mirror_reflects_environment() {
+        let mut map = Canvas::new(8, 4);
+        map.fill(Color::white());
+        let mut w = World::new();
+        w.lights.push(Light::environment(map.clone(), 16, Color::white()));
+        w.background = Background::Equirectangular(map);
+        let mut mirror = Shape::plane();
+        mirror.material.reflective = 1.0;
+        mirror.material.diffuse = 0.0;
+        mirror.material.ambient = 0.0;
+        w.add(mirror);
+
+        let r = Ray::new(point(0.0, 1.0, -1.0), vector(0.0, -1.0, 1.0).normalize());
+        assert_eq!(Color::white(), w.trace_path(&r, 0, MAX_DEPTH));
+    }
+
+    #[test]
+    fn path_traced_smooth_metal_reflects_emitter() {
+        let mut w = World::new();
+        let mut floor = Shape::plane();
+        floor.material = Material::cook_torrance();
+        floor.material.metallic = 1.0;
+        floor.material.roughness = 0.0;
+        w.add(floor);
+        let mut lamp = Shape::sphere();
+        lamp.material.emissive = Color::white();
+        lamp.material.diffuse = 0.0;
+        lamp.set_transform(translation(0.0, 3.0, 3.0));
+        w.add(lamp);
+        w.build_bvh();
+
+        let r = Ray::new(point(0.0, 1.0, -1.0), vector(0.0, -1.0, 1.0).normalize());
+        let samples = 2000;
+        let mut sum = Color::black();
+        for _ in 0..samples {
+            sum = sum + w.trace_path(&r, 0, MAX_DEPTH);
+        }
+
+        let mean = sum.red / samples as FP;
+        assert!((1.0 - mean).abs() < 0.1);
     }
 }